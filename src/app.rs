@@ -1,84 +1,573 @@
 mod area;
 mod canvas;
+mod command;
+mod dialog;
+mod input;
+mod keymap;
+mod layer;
 mod panel;
+mod recovery;
+#[cfg(test)]
+mod replay;
+mod status;
+#[cfg(unix)]
+mod tty;
 
-use std::io;
+use std::{
+    io::{self, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 use crossterm::{
-    event::{read, Event, KeyCode, MouseButton, MouseEvent, MouseEventKind},
-    terminal::{disable_raw_mode, enable_raw_mode, size},
+    event::{poll, read, Event, MouseButton, MouseEvent, MouseEventKind},
+    terminal::{disable_raw_mode, enable_raw_mode},
+};
+use paint_tui::{
+    format::{self, Format},
+    image::Image,
+    import::{self, ImportOptions},
+    Mode,
 };
+#[cfg(unix)]
+use signal_hook::consts::SIGHUP;
+use signal_hook::consts::SIGTERM;
 
-use crate::painter::Painter;
+use crate::{constant::symbols, painter::Painter, utils::xdg_dir};
 
 use self::{
     area::{Area, Corner, Point},
     canvas::Canvas,
+    command::{Command, CommandLine},
+    dialog::{Dialog, DialogResult},
+    keymap::{Action, Keymap},
+    layer::LayerPanel,
     panel::RightPanel,
+    recovery::Recovery,
+    status::StatusBar,
 };
 
+#[cfg(unix)]
+use self::tty::Tty;
+
+// Default file written when exporting canvas, ANSI text keeps colors.
+const EXPORT_PATH: &str = "paint-tui.ans";
+
+// Options the import dialog accepts, with their values or ranges.
+const IMPORT_HELP: &str = "\
+style=blocks|ascii   width=1..1000   aspect=0.1..10
+colors=true|256|none   dither=none|floyd|ordered
+contrast=0..10   brightness=-1..1   ramp=\"CHARACTERS\"";
+
+// How long the event loop waits for input before checking signals and autosave again.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+// How often unsaved changes are written to the recovery file.
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+// What an open dialog is asking for.
+enum Prompt {
+    // Save, discard or cancel, before quitting with unsaved changes.
+    Quit,
+    // Path to export to.
+    Export,
+    // Restore, discard or keep for later, a recovery copy left by a session that didn't exit.
+    Recovery,
+    // Options to convert this image with.
+    Import(Image),
+    // Switch to this mode, even though the drawing loses something.
+    Mode(Mode),
+}
+
 pub struct App {
     painter: Painter,
     right_panel: RightPanel,
     canvas: Canvas,
+    layer_panel: LayerPanel,
+    status_bar: StatusBar,
+    command_line: CommandLine,
+    keymap: Keymap,
+    // Invalid lines in the config file, shown on startup and printed after exit.
+    config_errors: Vec<String>,
+    // Gets all events while it's open.
+    dialog: Option<(Prompt, Dialog)>,
+    // Last path canvas was exported to.
+    export_path: String,
+    // Last options images were imported with.
+    import_options: ImportOptions,
+    // Where unsaved changes are written if they would be lost, None if there is nowhere to.
+    recovery: Option<Recovery>,
+    // When the recovery copy was last written (or the app started), and canvas changes by then.
+    autosaved: (Instant, u64),
 }
 
 impl App {
-    pub fn new(stdout: io::Stdout) -> App {
+    pub fn new(painter: Painter) -> App {
+        let (keymap, config_errors) =
+            Keymap::load(xdg_dir("XDG_CONFIG_HOME", ".config").as_deref());
+
         App {
-            painter: Painter::new(stdout),
+            painter,
             right_panel: RightPanel::new(Area::new(
                 Point::new(0, 0, Corner::TopLeft),
-                Point::new(4, 0, Corner::BottomLeft),
+                Point::new(4, 1, Corner::BottomLeft),
             )),
             canvas: Canvas::new(
                 Area::new(
                     Point::new(6, 1, Corner::TopLeft),
-                    Point::new(0, 0, Corner::_BottomRight),
+                    Point::new(22, 2, Corner::BottomRight),
                 ),
                 50,
                 20,
             ),
+            layer_panel: LayerPanel::new(Area::new(
+                Point::new(20, 0, Corner::TopRight),
+                Point::new(0, 1, Corner::BottomRight),
+            )),
+            status_bar: StatusBar::new(Area::new(
+                Point::new(0, 0, Corner::BottomLeft),
+                Point::new(0, 0, Corner::BottomRight),
+            )),
+            // Same area as status bar, it's drawn instead of status bar while it's open.
+            command_line: CommandLine::new(Area::new(
+                Point::new(0, 0, Corner::BottomLeft),
+                Point::new(0, 0, Corner::BottomRight),
+            )),
+            keymap,
+            config_errors,
+            dialog: None,
+            export_path: String::from(EXPORT_PATH),
+            import_options: ImportOptions::default(),
+            recovery: Recovery::in_state_dir(),
+            autosaved: (Instant::now(), 0),
         }
     }
 
     pub fn run(&mut self) -> crossterm::Result<()> {
+        // Set when paint-tui is asked to stop, e.g. by kill, or its terminal was closed.
+        let terminate = Arc::new(AtomicBool::new(false));
+        signal_hook::flag::register(SIGTERM, Arc::clone(&terminate))?;
+        #[cfg(unix)]
+        signal_hook::flag::register(SIGHUP, Arc::clone(&terminate))?;
+
+        // Waits until there is input, fails if the terminal is gone.
+        #[cfg(unix)]
+        let tty = Tty::open()?;
+        #[cfg(unix)]
+        let wait = |timeout| tty.wait(timeout);
+        // Elsewhere crossterm is the only way to read the terminal.
+        #[cfg(not(unix))]
+        let wait = |timeout| poll(timeout).map(|_| ());
+
         enable_raw_mode()?;
 
+        self.start()?;
+
+        loop {
+            // Killed, or the terminal is gone (with or without a SIGHUP), e.g. the SSH session died.
+            if terminate.load(Ordering::Relaxed) || wait(POLL_INTERVAL).is_err() {
+                self.stop();
+                return Ok(());
+            }
+
+            // Crossterm may have read several events at once, they are all handled before waiting.
+            loop {
+                let event =
+                    match poll(Duration::ZERO).and_then(|ready| ready.then(read).transpose()) {
+                        Ok(Some(event)) => event,
+                        Ok(None) => break,
+                        Err(_) => {
+                            self.stop();
+                            return Ok(());
+                        }
+                    };
+
+                if let Some(result) = self.handle_or_end(event, terminate.load(Ordering::Relaxed)) {
+                    return result;
+                }
+            }
+
+            self.autosave();
+        }
+    }
+
+    // Handles {event}, and returns what run returns if the app has to end after it.
+    // Drawing fails e.g. with EIO when the terminal is closed before the hangup is noticed,
+    // unsaved changes are kept in a recovery copy then. It isn't an error after a signal.
+    fn handle_or_end(&mut self, event: Event, terminated: bool) -> Option<crossterm::Result<()>> {
+        match self.handle_event(event) {
+            Ok(true) => Some(self.quit()),
+            Ok(false) => None,
+            Err(error) => {
+                self.stop();
+
+                Some(if terminated { Ok(()) } else { Err(error) })
+            }
+        }
+    }
+
+    // Exits after the user asked to.
+    fn quit(&mut self) -> crossterm::Result<()> {
+        self.exit()?;
+
+        if let Some(recovery) = &self.recovery {
+            // Not fatal, the recovery copy would just be offered again.
+            let _ = recovery.mark_clean_exit();
+        }
+
+        Ok(())
+    }
+
+    // Exits without asking, after writing a recovery copy of unsaved changes.
+    // The terminal may be gone, so failing to restore it or to print isn't an error.
+    fn stop(&mut self) {
+        let message = self.save_recovery();
+
+        let _ = self.exit();
+
+        if let Some(message) = message {
+            let _ = writeln!(io::stderr(), "{}", message);
+        }
+    }
+
+    // Writes a recovery copy if there are unsaved changes that weren't written in a while.
+    fn autosave(&mut self) {
+        let (time, changes) = self.autosaved;
+
+        if !self.canvas.is_dirty()
+            || changes == self.canvas.changes()
+            || time.elapsed() < AUTOSAVE_INTERVAL
+        {
+            return;
+        }
+
+        // Even if it failed, so the error isn't shown again on every poll.
+        self.autosaved = (Instant::now(), self.canvas.changes());
+
+        if let Some(recovery) = &self.recovery {
+            if let Err(error) = recovery.save(self.canvas.document()) {
+                self.status_bar.set_message(format!(
+                    "Can't write recovery copy {}: {}",
+                    recovery.path().display(),
+                    error
+                ));
+            }
+        }
+    }
+
+    // Writes a recovery copy of the canvas if it has unsaved changes, used when paint-tui
+    // has to stop without asking. Returns a message about it, for after the terminal is restored.
+    pub fn save_recovery(&self) -> Option<String> {
+        if !self.canvas.is_dirty() {
+            return None;
+        }
+
+        let Some(recovery) = &self.recovery else {
+            return Some(String::from(
+                "Unsaved changes are lost, there is no directory for a recovery copy",
+            ));
+        };
+
+        let path = recovery.path();
+
+        Some(match recovery.save(self.canvas.document()) {
+            Ok(()) => format!("Unsaved changes were saved to {}", path.display()),
+            Err(error) => format!(
+                "Unsaved changes are lost, can't write {}: {}",
+                path.display(),
+                error
+            ),
+        })
+    }
+
+    // Draws everything for the first time.
+    fn start(&mut self) -> crossterm::Result<()> {
         self.painter.start()?;
 
-        self.draw_all(size()?)?;
+        if let Some(error) = self.config_errors.first() {
+            self.status_bar.set_message(match self.config_errors.len() {
+                1 => error.clone(),
+                errors => format!("{} (and {} more errors)", error, errors - 1),
+            });
+        }
 
-        loop {
-            match read()? {
-                Event::Key(event) => {
-                    if event.code == KeyCode::Char('q') {
-                        return self.exit();
-                    }
+        self.draw_all(self.painter.size()?)?;
+
+        self.offer_recovery()
+    }
+
+    // Asks what to do with the recovery copy, if one was written since the last clean exit.
+    fn offer_recovery(&mut self) -> crossterm::Result<()> {
+        let Some(saved) = self.recovery.as_ref().and_then(Recovery::pending) else {
+            return Ok(());
+        };
+
+        let dialog = Dialog::new(
+            symbols::RECOVERY_TITLE,
+            &format!("Unsaved drawing from {}.", recovery::time_ago(saved)),
+            &[
+                symbols::RESTORE_BUTTON,
+                symbols::DISCARD_BUTTON,
+                symbols::KEEP_BUTTON,
+            ],
+        );
+
+        self.open_dialog(Prompt::Recovery, dialog)
+    }
+
+    // Applies the answer to the recovery dialog: restore (0), discard (1) or keep for later.
+    fn handle_recovery(&mut self, button: Option<usize>) -> crossterm::Result<()> {
+        let Some(recovery) = &self.recovery else {
+            return Ok(());
+        };
+
+        let message = match button {
+            Some(0) => match recovery.load() {
+                Ok(document) => {
+                    self.canvas.load(&mut self.painter, document)?;
+                    // It was never saved anywhere else.
+                    self.canvas.mark_changed();
+
+                    String::from("Restored unsaved drawing")
                 }
-                Event::Resize(width, height) => {
-                    self.painter.clear()?;
-                    self.draw_all((width, height))?;
+                Err(error) => format!("Can't open {}: {}", recovery.path().display(), error),
+            },
+            Some(1) => match recovery.discard() {
+                Ok(()) => String::from("Discarded unsaved drawing"),
+                Err(error) => format!("Can't remove {}: {}", recovery.path().display(), error),
+            },
+            // Autosave would overwrite it otherwise.
+            _ => match recovery.keep() {
+                Ok(path) => format!("Unsaved drawing was moved to {}", path.display()),
+                Err(error) => format!("Can't move {}: {}", recovery.path().display(), error),
+            },
+        };
+
+        self.status_bar.set_message(message);
+
+        Ok(())
+    }
+
+    // Returns true if the app should exit.
+    fn handle_event(&mut self, event: Event) -> crossterm::Result<bool> {
+        match event {
+            Event::Resize(width, height) if self.dialog.is_some() => {
+                self.painter.clear()?;
+                self.draw_all((width, height))?;
+                self.draw_dialog((width, height))?;
+            }
+            event if self.dialog.is_some() => {
+                let exit = self.dialog_event(event)?;
+
+                if exit {
+                    return Ok(true);
+                }
+            }
+            Event::Key(event) if self.command_line.is_open() => {
+                if let Some(text) = self.command_line.key_event(event) {
+                    self.run_command(&text)?;
                 }
-                Event::Mouse(event) => {
-                    self.handle_mouse_event(event)?;
+            }
+            Event::Key(event) => {
+                let keyboard = self.canvas.cursor().is_some();
+
+                match self.keymap.action(event, keyboard) {
+                    Some(Action::Quit) if self.canvas.is_dirty() => {
+                        let message = match self.export_loses().as_str() {
+                            "" => String::from("There are unsaved changes."),
+                            lost => format!(
+                                "There are unsaved changes.\nSaving to {} loses {}.",
+                                self.export_path, lost
+                            ),
+                        };
+                        let dialog = Dialog::new(
+                            symbols::QUIT_TITLE,
+                            message.as_str(),
+                            &[
+                                symbols::SAVE_BUTTON,
+                                symbols::DISCARD_BUTTON,
+                                symbols::CANCEL_BUTTON,
+                            ],
+                        );
+
+                        self.open_dialog(Prompt::Quit, dialog)?;
+                    }
+                    Some(Action::Quit) => return Ok(true),
+                    Some(action) => self.handle_action(action)?,
+                    None => {}
                 }
-                _ => {}
             }
+            Event::Resize(width, height) => {
+                self.painter.clear()?;
+                self.draw_all((width, height))?;
+            }
+            Event::Mouse(event) => {
+                self.handle_mouse_event(event)?;
+            }
+            _ => {}
         }
+
+        self.draw_status(self.painter.size()?)?;
+
+        Ok(false)
     }
 
     fn draw_all(&mut self, t_size: (u16, u16)) -> crossterm::Result<()> {
+        // Borders are drawn first, so components can draw on top of them (e.g. scroll indicators).
+        self.draw_borders(t_size)?;
+
         self.right_panel.draw(&mut self.painter, t_size)?;
         self.canvas.draw(&mut self.painter, t_size)?;
+        self.draw_layer_panel(t_size)?;
+        self.draw_status(t_size)
+    }
 
-        self.draw_borders(t_size)?;
+    fn open_dialog(&mut self, prompt: Prompt, dialog: Dialog) -> crossterm::Result<()> {
+        self.canvas.clear_hover(&mut self.painter)?;
+        self.dialog = Some((prompt, dialog));
+
+        self.draw_dialog(self.painter.size()?)
+    }
+
+    fn draw_dialog(&mut self, t_size: (u16, u16)) -> crossterm::Result<()> {
+        match &mut self.dialog {
+            Some((_, dialog)) => dialog.draw(&mut self.painter, t_size),
+            None => Ok(()),
+        }
+    }
+
+    // Passes {event} to the open dialog, and applies the result if it was closed.
+    // Returns true if the app should exit.
+    fn dialog_event(&mut self, event: Event) -> crossterm::Result<bool> {
+        let result = match (&mut self.dialog, event) {
+            (Some((_, dialog)), Event::Key(event)) => dialog.key_event(event, &mut self.painter)?,
+            (Some((_, dialog)), Event::Mouse(event)) => {
+                dialog.mouse_event(event, &mut self.painter)?
+            }
+            _ => None,
+        };
+
+        let Some(result) = result else {
+            return Ok(false);
+        };
+
+        let Some((prompt, _)) = self.dialog.take() else {
+            return Ok(false);
+        };
 
-        self.painter.flush()?;
+        let exit = match (prompt, result) {
+            (Prompt::Quit, DialogResult::Button(0, _)) => self.save_or_show_error(),
+            (Prompt::Quit, DialogResult::Button(1, _)) => true,
+            (Prompt::Export, DialogResult::Button(0, path)) => {
+                self.export_path = path;
+                self.save_or_show_error();
+                false
+            }
+            (Prompt::Recovery, DialogResult::Button(index, _)) => {
+                self.handle_recovery(Some(index))?;
+                false
+            }
+            (Prompt::Recovery, DialogResult::Cancel) => {
+                self.handle_recovery(None)?;
+                false
+            }
+            (Prompt::Mode(mode), DialogResult::Button(0, _)) => {
+                self.canvas.set_mode(&mut self.painter, mode)?;
+                false
+            }
+            (Prompt::Import(image), DialogResult::Button(0, options)) => {
+                match ImportOptions::parse(&options) {
+                    Ok(options) => {
+                        let document = import::convert(&image, &options);
+
+                        self.canvas.load(&mut self.painter, document)?;
+                        // The drawing isn't saved anywhere yet.
+                        self.canvas.mark_changed();
+                        self.status_bar.set_message("Imported image");
+                        self.import_options = options;
+                    }
+                    Err(error) => self.status_bar.set_message(error),
+                }
+                false
+            }
+            _ => false,
+        };
+
+        // Draw everything again, to remove the dialog.
+        self.painter.clear()?;
+        self.draw_all(self.painter.size()?)?;
+
+        Ok(exit)
+    }
+
+    // Writes the canvas to export_path, in the format for its extension (see format::Format).
+    // Returns a message if it couldn't be written.
+    fn save(&mut self) -> Result<(), String> {
+        format::save(self.canvas.document(), &self.export_path)
+            .map_err(|error| format!("Can't export to {}: {}", self.export_path, error))?;
+
+        self.canvas.mark_saved();
+        self.status_bar
+            .set_message(match self.export_loses().as_str() {
+                "" => format!("Exported to {}", self.export_path),
+                lost => format!("Exported to {}, without {}", self.export_path, lost),
+            });
 
         Ok(())
     }
 
+    // What exporting the canvas to export_path loses, e.g. "colors and layers", empty if nothing.
+    fn export_loses(&self) -> String {
+        let lost = Format::from_path(Path::new(&self.export_path)).lost(self.canvas.document());
+
+        match lost.split_last() {
+            Some((last, [])) => last.to_string(),
+            Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+            None => String::new(),
+        }
+    }
+
+    // Same as save, but the message is shown in the status bar. Returns whether it worked.
+    fn save_or_show_error(&mut self) -> bool {
+        match self.save() {
+            Ok(()) => true,
+            Err(error) => {
+                self.status_bar.set_message(error);
+                false
+            }
+        }
+    }
+
+    fn draw_layer_panel(&mut self, t_size: (u16, u16)) -> crossterm::Result<()> {
+        self.layer_panel.draw(
+            &mut self.painter,
+            t_size,
+            self.canvas.layers(),
+            self.canvas.active_layer(),
+        )
+    }
+
+    fn draw_status(&mut self, t_size: (u16, u16)) -> crossterm::Result<()> {
+        if self.command_line.is_open() {
+            self.command_line.draw(&mut self.painter, t_size)?;
+
+            return self.painter.flush();
+        }
+
+        self.status_bar.draw(
+            &mut self.painter,
+            t_size,
+            &self.canvas,
+            self.right_panel.get_tool(),
+            &self.right_panel.brush,
+        )?;
+
+        self.painter.flush()
+    }
+
     // Draw major borders.
     fn draw_borders(&mut self, t_size: (u16, u16)) -> crossterm::Result<()> {
         // Canvas border
@@ -98,10 +587,13 @@ impl App {
     }
 
     fn handle_mouse_event(&mut self, event: MouseEvent) -> crossterm::Result<()> {
-        let t_size = size()?;
+        let t_size = self.painter.size()?;
 
         let (x, y) = (event.column, event.row);
 
+        self.status_bar
+            .set_position(self.canvas.content_position(x, y));
+
         // Canvas is the only component that handles release events,
         // and it needs to handle them regardless of the position.
         if let MouseEventKind::Up(MouseButton::Left) = event.kind {
@@ -112,9 +604,37 @@ impl App {
             );
         }
 
+        if let MouseEventKind::Drag(MouseButton::Left) = event.kind {
+            if self.canvas.is_dragging() {
+                return self.canvas.mouse_event(
+                    event,
+                    &mut self.painter,
+                    self.right_panel.get_tool(),
+                    &self.right_panel.brush,
+                );
+            }
+        }
+
+        if !self.canvas.area.check_inside(x, y, t_size) {
+            // Pointer is outside the canvas, remove brush preview.
+            self.canvas.clear_hover(&mut self.painter)?;
+        }
+
         if self.right_panel.area.check_inside(x, y, t_size) {
             self.right_panel.mouse_event(event, &mut self.painter)?;
+        } else if self.layer_panel.area.check_inside(x, y, t_size) {
+            let total_layers = self.canvas.layers().len();
+
+            if let Some(action) = self.layer_panel.mouse_event(event, total_layers) {
+                self.canvas.layer_action(&mut self.painter, action)?;
+            }
+
+            self.draw_layer_panel(t_size)?;
         } else if self.canvas.area.check_inside(x, y, t_size) {
+            if let MouseEventKind::Down(MouseButton::Left) = event.kind {
+                self.warn_if_unusable();
+            }
+
             self.canvas.mouse_event(
                 event,
                 &mut self.painter,
@@ -126,11 +646,219 @@ impl App {
         Ok(())
     }
 
+    fn handle_action(&mut self, action: Action) -> crossterm::Result<()> {
+        match action {
+            // Handled in handle_event, because it ends the app.
+            Action::Quit => Ok(()),
+            Action::Tool(tool) => self.right_panel.select_tool(&mut self.painter, tool),
+            Action::ZoomIn => self.canvas.zoom_center(&mut self.painter, 1),
+            Action::ZoomOut => self.canvas.zoom_center(&mut self.painter, -1),
+            Action::Pan(dx, dy) => self.canvas.pan(&mut self.painter, dx, dy),
+            Action::ToggleMode => self.switch_mode(self.canvas.document().mode().next()),
+            Action::ToggleGrid => self.canvas.toggle_grid(&mut self.painter),
+            Action::Export => {
+                let dialog = Dialog::prompt(
+                    symbols::EXPORT_TITLE,
+                    "Export to .txt (plain text), .ans (colors) or .ptui (layers):",
+                    &self.export_path,
+                    &[symbols::EXPORT_BUTTON, symbols::CANCEL_BUTTON],
+                );
+
+                self.open_dialog(Prompt::Export, dialog)
+            }
+            Action::ToggleKeyboard => {
+                self.canvas.toggle_cursor(&mut self.painter)?;
+                self.status_bar.set_position(self.canvas.cursor());
+                self.status_bar
+                    .set_message(if self.canvas.cursor().is_some() {
+                        "Keyboard drawing on, arrows/hjkl move"
+                    } else {
+                        "Keyboard drawing off"
+                    });
+                Ok(())
+            }
+            Action::Cursor(dx, dy) => self.move_cursor(dx, dy),
+            Action::Press => {
+                if self.canvas.cursor().is_some() {
+                    self.warn_if_unusable();
+                }
+
+                self.canvas.press_cursor(
+                    &mut self.painter,
+                    self.right_panel.get_tool(),
+                    &self.right_panel.brush,
+                )
+            }
+            Action::Release => self.canvas.release_cursor(
+                &mut self.painter,
+                self.right_panel.get_tool(),
+                &self.right_panel.brush,
+            ),
+            Action::Command => {
+                self.command_line.open();
+                Ok(())
+            }
+            Action::MouseTarget => {
+                self.canvas.next_mouse_target();
+                Ok(())
+            }
+            Action::Undo => {
+                if self.canvas.undo(&mut self.painter)? {
+                    self.draw_layer_panel(self.painter.size()?)
+                } else {
+                    self.status_bar.set_message("Nothing to undo");
+                    Ok(())
+                }
+            }
+            Action::Save => {
+                self.save_or_show_error();
+                Ok(())
+            }
+        }
+    }
+
+    // Runs command line {text}. Command line is closed if it worked, otherwise it shows why not.
+    fn run_command(&mut self, text: &str) -> crossterm::Result<()> {
+        let result = match Command::parse(text) {
+            _ if text.trim().is_empty() => Ok(()),
+            Ok(command) => self.execute(command)?,
+            Err(error) => Err(error),
+        };
+
+        match result {
+            Ok(()) => self.command_line.close(),
+            Err(error) => self.command_line.set_error(error),
+        }
+
+        Ok(())
+    }
+
+    // Returns a message if {command} can't be done.
+    fn execute(&mut self, command: Command) -> crossterm::Result<Result<(), String>> {
+        let painter = &mut self.painter;
+
+        match command {
+            Command::Write(path) => {
+                if let Some(path) = path {
+                    self.export_path = path;
+                }
+
+                return Ok(self.save());
+            }
+            Command::Edit { path, force } => {
+                if self.canvas.is_dirty() && !force {
+                    return Ok(Err(String::from(
+                        "There are unsaved changes, use :e! to discard them",
+                    )));
+                }
+
+                let document = match format::load(&path) {
+                    Ok(document) => document,
+                    Err(error) => return Ok(Err(format!("Can't open {}: {}", path, error))),
+                };
+
+                self.canvas.load(painter, document)?;
+                self.status_bar.set_message(format!("Opened {}", path));
+                self.export_path = path;
+            }
+            Command::Import { path, force } => {
+                if self.canvas.is_dirty() && !force {
+                    return Ok(Err(String::from(
+                        "There are unsaved changes, use :import! to discard them",
+                    )));
+                }
+
+                let image = match Image::load(&path) {
+                    Ok(image) => image,
+                    Err(error) => return Ok(Err(format!("Can't open {}: {}", path, error))),
+                };
+                let (width, height) = image.size();
+
+                let dialog = Dialog::prompt(
+                    symbols::IMPORT_TITLE,
+                    &format!(
+                        "Import {}x{} image with options:\n{}",
+                        width, height, IMPORT_HELP
+                    ),
+                    &self.import_options.to_string(),
+                    &[symbols::IMPORT_BUTTON, symbols::CANCEL_BUTTON],
+                );
+
+                self.open_dialog(Prompt::Import(image), dialog)?;
+            }
+            Command::Resize(width, height) => self.canvas.resize(painter, width, height)?,
+            Command::Fill | Command::Clear if !self.canvas.editable() => {
+                return Ok(Err(String::from("Active layer is hidden or locked")));
+            }
+            Command::Fill => self
+                .canvas
+                .fill_layer(painter, Some(&self.right_panel.brush))?,
+            Command::Clear => self.canvas.fill_layer(painter, None)?,
+            Command::Tool(tool) => self.right_panel.select_tool(painter, tool)?,
+            Command::Grid(grid) => self.canvas.set_grid(painter, grid)?,
+            Command::GridSpacing(spacing) => self.canvas.set_grid_spacing(painter, spacing)?,
+        }
+
+        Ok(Ok(()))
+    }
+
+    fn move_cursor(&mut self, dx: i16, dy: i16) -> crossterm::Result<()> {
+        self.canvas.move_cursor(
+            &mut self.painter,
+            self.right_panel.get_tool(),
+            &self.right_panel.brush,
+            dx,
+            dy,
+        )?;
+
+        self.status_bar.set_position(self.canvas.cursor());
+
+        Ok(())
+    }
+
+    // Switches the canvas to {mode}, asking first if the drawing loses something it can't get back.
+    fn switch_mode(&mut self, mode: Mode) -> crossterm::Result<()> {
+        if !self.canvas.document().mode_change_loses(mode) {
+            return self.canvas.set_mode(&mut self.painter, mode);
+        }
+
+        // Braille dots have no colors, other modes only lose characters that aren't blocks.
+        let lost = match mode {
+            Mode::Braille => "colors and characters",
+            _ => "characters it can't show",
+        };
+        let dialog = Dialog::new(
+            symbols::MODE_TITLE,
+            &format!(
+                "Switching to {} mode loses\n{}.\n\
+                 Switching back doesn't restore them.",
+                mode.name(),
+                lost
+            ),
+            &[symbols::SWITCH_BUTTON, symbols::CANCEL_BUTTON],
+        );
+
+        self.open_dialog(Prompt::Mode(mode), dialog)
+    }
+
+    // Tells why the selected tool only selects, instead of silently falling back to Select.
+    fn warn_if_unusable(&mut self) {
+        if !self.canvas.can_use(self.right_panel.get_tool()) {
+            self.status_bar
+                .set_message("Active layer is hidden or locked, tool only selects");
+        }
+    }
+
     fn exit(&mut self) -> crossterm::Result<()> {
         self.painter.stop()?;
 
         disable_raw_mode()?;
 
+        // Alternate screen is gone, so these stay visible in the terminal.
+        for error in &self.config_errors {
+            eprintln!("{}", error);
+        }
+
         Ok(())
     }
 }
@@ -65,9 +65,9 @@ impl Point {
 
         match self.corner {
             Corner::TopLeft => (self.x, self.y),
-            Corner::_TopRight => (t_width.diff_or_zero(&self.x), self.y),
+            Corner::TopRight => (t_width.diff_or_zero(&self.x), self.y),
             Corner::BottomLeft => (self.x, t_height.diff_or_zero(&self.y)),
-            Corner::_BottomRight => (
+            Corner::BottomRight => (
                 t_width.diff_or_zero(&self.x),
                 t_height.diff_or_zero(&self.y),
             ),
@@ -78,7 +78,7 @@ impl Point {
 // Origin point location for a particular Point instance.
 pub enum Corner {
     TopLeft,
-    _TopRight,
+    TopRight,
     BottomLeft,
-    _BottomRight,
+    BottomRight,
 }
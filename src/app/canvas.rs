@@ -1,19 +1,63 @@
-use std::{cmp::min, vec};
+use std::cmp::{max, min};
 
 use crossterm::{
-    event::{MouseButton, MouseEvent, MouseEventKind},
-    style::{ContentStyle, StyledContent},
+    event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
+    style::{Color, StyledContent, Stylize},
 };
+use paint_tui::{shape, Cell, Document, Layer, Mode, Selection};
 
-use crate::{painter::Painter, utils::AddSubOrZero};
+use crate::{
+    constant::symbols,
+    painter::Painter,
+    utils::{AddSubOrZero, DiffOrZero},
+};
+
+use super::{area::Area, layer::LayerAction, panel::Tool};
+
+// Size of one content cell in terminal cells, for each zoom level.
+// Terminal cells are roughly twice as tall as they are wide, so width grows faster.
+const ZOOM_LEVELS: [(u16, u16); 4] = [(1, 1), (2, 1), (4, 2), (8, 4)];
 
-use super::{area::Area, panel::Tool};
+// Number of changes undo remembers, older ones are forgotten.
+const UNDO_LIMIT: usize = 100;
+
+// Default number of content cells between grid lines.
+const DEFAULT_GRID_SPACING: usize = 5;
 
 pub struct Canvas {
     pub area: Area,
-    content: Vec<Vec<StyledContent<char>>>,
-    // Use with tools that operate on multiple mouse events.
-    active: Option<Active>,
+    document: Document,
+    // Use with tools that operate on multiple mouse events. The Select tool uses
+    // the document selection instead.
+    active: Option<Selection>,
+    // Content position of the top left visible cell, used when content is bigger than the viewport.
+    offset: (usize, usize),
+    // Index in ZOOM_LEVELS.
+    zoom: usize,
+    // Content position where the brush preview is drawn, see hover.
+    hover: Option<(usize, usize)>,
+    // Last mouse position of a middle button drag, used for panning.
+    pan_anchor: Option<(u16, u16)>,
+    // Whether grid and rulers are drawn over content.
+    grid: bool,
+    // Number of content cells between grid lines.
+    grid_spacing: usize,
+    // Whether the current mouse event targets the other half of a terminal cell (Alt is held),
+    // see mouse_target.
+    lower_half: bool,
+    // Index of the content cell the mouse targets, in terminal cells with more than one of them
+    // (see next_mouse_target). Terminals only report whole cells, so it has to be picked explicitly.
+    target: u16,
+    // Content position of the keyboard cursor, None if keyboard drawing is off.
+    cursor: Option<(usize, usize)>,
+    // Whether the keyboard cursor is pressed, so moving it drags the tool (like a mouse button).
+    cursor_pressed: bool,
+    // Whether content changed since it was last saved.
+    dirty: bool,
+    // Number of changes to content, so autosave can tell if there is anything new.
+    changes: u64,
+    // Document before each change, the last one is restored by undo.
+    undo: Vec<Document>,
     // Fields that depends on current terminal window size.
     relative: Relative,
 }
@@ -22,185 +66,1047 @@ impl Canvas {
     // {content_width} and {content_height} are the size of the drawing area,
     // which is different to {area}, the size of the Canvas widget/component.
     pub fn new(area: Area, content_width: usize, content_height: usize) -> Canvas {
-        let content = vec![vec![Self::empty(); content_width]; content_height];
-
         Canvas {
             area,
-            content,
+            document: Document::new(content_width, content_height),
             active: None,
+            offset: (0, 0),
+            zoom: 0,
+            hover: None,
+            pan_anchor: None,
+            grid: false,
+            grid_spacing: DEFAULT_GRID_SPACING,
+            lower_half: false,
+            target: 0,
+            cursor: None,
+            cursor_pressed: false,
+            dirty: false,
+            changes: 0,
+            undo: Vec::new(),
             relative: Relative::zero(),
         }
-    }
+    }
+
+    pub fn draw(&mut self, painter: &mut Painter, t_size: (u16, u16)) -> crossterm::Result<()> {
+        self.set_relative(t_size);
+
+        self.draw_border(painter)?;
+
+        self.draw_content(painter)
+    }
+
+    // Number of content columns and rows in one terminal cell at the smallest zoom level.
+    fn sub_cells(&self) -> (u16, u16) {
+        self.document.mode().sub_cells()
+    }
+
+    // Size of one content cell in sub cells (see sub_cells).
+    fn cell_size(&self) -> (u16, u16) {
+        let (width, height) = ZOOM_LEVELS[self.zoom];
+
+        match self.document.mode() {
+            Mode::Text => (width, height),
+            // Sub cells in these modes are roughly square, so pixels and dots stay square.
+            Mode::HalfBlock | Mode::Braille => (width, width),
+        }
+    }
+
+    fn set_relative(&mut self, t_size: (u16, u16)) {
+        let (x, y) = self.area.start.absolute_position(t_size);
+        let (width, height) = self.area.size(t_size);
+        let (cell_width, cell_height) = self.cell_size();
+        let (sub_columns, sub_rows) = self.sub_cells();
+        let (content_width, content_height) = self.size();
+
+        // Number of content cells that fit in the viewport, at least one so something is visible.
+        let fit_width = usize::from(width * sub_columns / cell_width).max(1);
+        let fit_height = usize::from(height * sub_rows / cell_height).max(1);
+
+        // Keep offset inside content, in case the viewport grew or content shrunk.
+        self.offset.0 = min(self.offset.0, content_width.saturating_sub(fit_width));
+        self.offset.1 = min(self.offset.1, content_height.saturating_sub(fit_height));
+
+        // Rounded up, so a terminal cell only partially covered by content is still visible.
+        let visible_content_width = min(
+            ((content_width - self.offset.0) * usize::from(cell_width))
+                .div_ceil(usize::from(sub_columns)),
+            usize::from(width),
+        ) as u16;
+        let visible_content_height = min(
+            ((content_height - self.offset.1) * usize::from(cell_height))
+                .div_ceil(usize::from(sub_rows)),
+            usize::from(height),
+        ) as u16;
+
+        // Content smaller than the viewport is centered, bigger content starts at area start.
+        let content_start_x = x + width.diff_or_zero(&visible_content_width) / 2;
+        let content_start_y = y + height.diff_or_zero(&visible_content_height) / 2;
+
+        self.relative = Relative::new(
+            (visible_content_width, visible_content_height),
+            (content_start_x, content_start_y),
+            t_size,
+        );
+    }
+
+    // Moves the viewport by (dx, dy) content cells, then redraws the canvas.
+    pub fn pan(&mut self, painter: &mut Painter, dx: i16, dy: i16) -> crossterm::Result<()> {
+        let offset = (
+            self.offset.0.add_sub_or_zero(&dx),
+            self.offset.1.add_sub_or_zero(&dy),
+        );
+
+        if offset == self.offset {
+            return Ok(());
+        }
+
+        // set_relative clamps the offset, check again if the viewport actually moved.
+        let previous_offset = self.offset;
+        self.offset = offset;
+        self.set_relative(self.relative.t_size);
+
+        if previous_offset == self.offset {
+            return Ok(());
+        }
+
+        self.draw_border(painter)?;
+        self.draw_content(painter)?;
+        painter.flush()
+    }
+
+    // Changes zoom level by {steps}, keeping the content cell under (x, y) in place.
+    pub fn zoom(
+        &mut self,
+        painter: &mut Painter,
+        steps: i16,
+        x: u16,
+        y: u16,
+    ) -> crossterm::Result<()> {
+        let zoom = min(self.zoom.add_sub_or_zero(&steps), ZOOM_LEVELS.len() - 1);
+
+        if zoom == self.zoom {
+            return Ok(());
+        }
+
+        let anchor = self.apply_transform(x, y);
+
+        self.zoom = zoom;
+        self.cancel_drag();
+
+        if let Some((content_x, content_y)) = anchor {
+            let (area_x, area_y) = self.area.start.absolute_position(self.relative.t_size);
+            let (cell_width, cell_height) = self.cell_size();
+            let (sub_columns, sub_rows) = self.sub_cells();
+            let sub_x = x.diff_or_zero(&area_x) * sub_columns;
+            let sub_y = y.diff_or_zero(&area_y) * sub_rows;
+
+            self.offset = (
+                content_x.diff_or_zero(&usize::from(sub_x / cell_width)),
+                content_y.diff_or_zero(&usize::from(sub_y / cell_height)),
+            );
+        }
+
+        self.set_relative(self.relative.t_size);
+
+        self.redraw(painter)
+    }
+
+    // Zoom centered on the middle of the viewport, used when there is no pointer position.
+    pub fn zoom_center(&mut self, painter: &mut Painter, steps: i16) -> crossterm::Result<()> {
+        let (start_x, start_y) = self.relative.content_start;
+        let (visible_width, visible_height) = self.relative.visible_content_size;
+
+        self.zoom(
+            painter,
+            steps,
+            start_x + visible_width / 2,
+            start_y + visible_height / 2,
+        )
+    }
+
+    // Switches to {mode}, converting content (see Document::set_mode).
+    pub fn set_mode(&mut self, painter: &mut Painter, mode: Mode) -> crossterm::Result<()> {
+        let (old_columns, old_rows) = self.sub_cells();
+
+        self.checkpoint();
+        self.document.set_mode(mode);
+
+        // Content size changed by the same ratio as sub cells, keep positions on the same cells.
+        let (sub_columns, sub_rows) = self.sub_cells();
+        let scale = |(x, y): (usize, usize)| {
+            (
+                x * usize::from(sub_columns) / usize::from(old_columns),
+                y * usize::from(sub_rows) / usize::from(old_rows),
+            )
+        };
+
+        self.cursor = self.cursor.map(scale);
+        self.offset = scale(self.offset);
+        self.cancel_drag();
+        self.hover = None;
+        self.cursor_pressed = false;
+        self.mark_changed();
+        self.set_relative(self.relative.t_size);
+
+        self.redraw(painter)
+    }
+
+    // Whether content changed since it was last saved.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.dirty = false;
+    }
+
+    pub fn mark_changed(&mut self) {
+        self.dirty = true;
+        self.changes += 1;
+    }
+
+    // Remembers content before a change, so undo can restore it.
+    // Called once per change, e.g. at the start of a brush stroke.
+    fn checkpoint(&mut self) {
+        if self.undo.len() == UNDO_LIMIT {
+            self.undo.remove(0);
+        }
+
+        self.undo.push(self.document.clone());
+    }
+
+    // Restores content from before the last change. Returns false if there is nothing to undo.
+    pub fn undo(&mut self, painter: &mut Painter) -> crossterm::Result<bool> {
+        let Some(document) = self.undo.pop() else {
+            return Ok(false);
+        };
+
+        self.document = document;
+        self.reset_content_state();
+        self.redraw(painter)?;
+
+        Ok(true)
+    }
+
+    // Number of changes since canvas was created, it only goes up.
+    pub fn changes(&self) -> u64 {
+        self.changes
+    }
+
+    pub fn layers(&self) -> &[Layer] {
+        self.document.layers()
+    }
+
+    pub fn active_layer(&self) -> usize {
+        self.document.active_layer()
+    }
+
+    // Applies a change requested from LayerPanel, then draws content again.
+    pub fn layer_action(
+        &mut self,
+        painter: &mut Painter,
+        action: LayerAction,
+    ) -> crossterm::Result<()> {
+        // Everything else changes the drawing.
+        if !matches!(action, LayerAction::Select(_)) {
+            self.checkpoint();
+            self.mark_changed();
+        }
+
+        match action {
+            LayerAction::Select(index) => self.document.set_active_layer(index),
+            LayerAction::ToggleVisible(index) => self.document.toggle_visible(index),
+            LayerAction::ToggleLock(index) => self.document.toggle_lock(index),
+            LayerAction::Add => self.document.add_layer(),
+            LayerAction::Delete => self.document.delete_layer(),
+            LayerAction::MoveUp => self.document.move_layer_up(),
+            LayerAction::MoveDown => self.document.move_layer_down(),
+        }
+
+        self.cancel_drag();
+        self.hover = None;
+
+        self.draw_content(painter)?;
+        painter.flush()
+    }
+
+    // Whether tools can draw on the active layer.
+    pub fn editable(&self) -> bool {
+        self.document.editable()
+    }
+
+    // Sets every cell of the active layer to {brush}, or to Cell::Empty if None.
+    pub fn fill_layer(
+        &mut self,
+        painter: &mut Painter,
+        brush: Option<&StyledContent<char>>,
+    ) -> crossterm::Result<()> {
+        let cell = match brush {
+            Some(brush) => self.document.brush(brush),
+            None => Cell::Empty,
+        };
+
+        self.checkpoint();
+        self.document.fill(cell);
+        self.mark_changed();
+
+        self.draw_content(painter)?;
+        painter.flush()
+    }
+
+    // Changes content size of every layer, new cells are empty.
+    pub fn resize(
+        &mut self,
+        painter: &mut Painter,
+        width: usize,
+        height: usize,
+    ) -> crossterm::Result<()> {
+        self.checkpoint();
+        self.document.resize(width, height);
+        self.reset_content_state();
+
+        self.redraw(painter)
+    }
+
+    pub fn document(&self) -> &Document {
+        &self.document
+    }
+
+    // Replaces everything with {document}, like it was just opened.
+    pub fn load(&mut self, painter: &mut Painter, document: Document) -> crossterm::Result<()> {
+        self.document = document;
+        self.offset = (0, 0);
+        self.undo.clear();
+
+        self.reset_content_state();
+        self.dirty = false;
+
+        self.redraw(painter)
+    }
+
+    // Drops state that refers to old content, after content size changed.
+    fn reset_content_state(&mut self) {
+        let (width, height) = self.size();
+
+        self.cancel_drag();
+        self.hover = None;
+        self.cursor_pressed = false;
+        self.cursor = self
+            .cursor
+            .map(|(x, y)| (min(x, width - 1), min(y, height - 1)));
+        self.mark_changed();
+
+        self.set_relative(self.relative.t_size);
+    }
+
+    // Clears the whole canvas area and draws everything again.
+    // Used when visible content size changes, so old content outside the new size is removed.
+    fn redraw(&mut self, painter: &mut Painter) -> crossterm::Result<()> {
+        let t_size = self.relative.t_size;
+        let (x, y) = self.area.start.absolute_position(t_size);
+        let (width, height) = self.area.size(t_size);
+
+        painter.fill(x, y, width, height, ' ')?;
+
+        if x > 0 && y > 0 {
+            painter.draw_box(x - 1, y - 1, width + 2, height + 2)?;
+        }
+
+        self.draw(painter, t_size)?;
+        painter.flush()
+    }
+
+    // Content size, in content cells.
+    pub fn size(&self) -> (usize, usize) {
+        self.document.size()
+    }
+
+    // Rectangle being selected or dragged by a shape tool, None if there is none.
+    fn dragged_rectangle(&self) -> Option<Selection> {
+        self.document.selection().or(self.active)
+    }
+
+    // Size of the rectangle being selected, None if nothing is being selected.
+    pub fn selection_size(&self) -> Option<(usize, usize)> {
+        self.dragged_rectangle().map(|selection| selection.size())
+    }
+
+    // Content position at absolute position (x, y), None if it's outside visible content.
+    pub fn content_position(&self, x: u16, y: u16) -> Option<(usize, usize)> {
+        self.apply_transform(x, y)
+    }
+
+    // Transforms absolute position to content position.
+    // Returns None if parameters can't be converted.
+    // (If position is outside visible content).
+    fn apply_transform(&self, x: u16, y: u16) -> Option<(usize, usize)> {
+        let (start_x, start_y) = self.relative.content_start;
+        let (visible_width, visible_height) = self.relative.visible_content_size;
+        let (cell_width, cell_height) = self.cell_size();
+        let (sub_columns, sub_rows) = self.sub_cells();
+
+        if x < start_x
+            || y < start_y
+            || x >= start_x + visible_width
+            || y >= start_y + visible_height
+        {
+            return None;
+        }
+
+        let (column, row) = self.mouse_cell();
+        let sub_x = (x - start_x) * sub_columns + column * cell_width;
+        let sub_y = (y - start_y) * sub_rows + row * cell_height;
+
+        let result_x = self.offset.0 + usize::from(sub_x / cell_width);
+        let result_y = self.offset.1 + usize::from(sub_y / cell_height);
+
+        // Last terminal column or row may only be partially inside content.
+        let (content_width, content_height) = self.size();
+
+        if result_x >= content_width || result_y >= content_height {
+            return None;
+        }
+
+        Some((result_x, result_y))
+    }
+
+    // Number of content columns and rows in one terminal cell at the current zoom, at least 1.
+    fn cells_per_cell(&self) -> (u16, u16) {
+        let (cell_width, cell_height) = self.cell_size();
+        let (sub_columns, sub_rows) = self.sub_cells();
+
+        (
+            max(sub_columns / cell_width, 1),
+            max(sub_rows / cell_height, 1),
+        )
+    }
+
+    // Column and row inside a terminal cell the mouse targets, Alt targets the other half.
+    fn mouse_cell(&self) -> (u16, u16) {
+        let (columns, rows) = self.cells_per_cell();
+        let target = self.target % (columns * rows);
+        let flip = if self.lower_half { rows / 2 } else { 0 };
+
+        (target % columns, (target / columns + flip) % rows)
+    }
+
+    // Makes the mouse target the next content cell inside terminal cells (e.g. the lower pixel,
+    // or the next braille dot), left to right and then top to bottom.
+    pub fn next_mouse_target(&mut self) {
+        let (columns, rows) = self.cells_per_cell();
+
+        self.target = (self.target + 1) % (columns * rows);
+    }
+
+    // Which part of a terminal cell the mouse draws on, for the status bar.
+    // None if every terminal cell is a single content cell.
+    pub fn mouse_target(&self) -> Option<String> {
+        let (columns, rows) = self.cells_per_cell();
+        let (column, row) = self.mouse_cell();
+
+        match self.document.mode() {
+            _ if columns * rows == 1 => None,
+            Mode::HalfBlock if row == 0 => Some(String::from("Mouse: upper half, F5/Alt: lower")),
+            Mode::HalfBlock => Some(String::from("Mouse: lower half, F5/Alt: upper")),
+            _ => Some(format!(
+                "Mouse: dot {},{} of {}x{}, F5: next",
+                column + 1,
+                row + 1,
+                columns,
+                rows
+            )),
+        }
+    }
+
+    // Same as apply_transform, but a position outside the viewport gives the closest visible cell.
+    fn clamped_transform(&self, x: u16, y: u16) -> Option<(usize, usize)> {
+        let (start_x, start_y) = self.relative.content_start;
+        let (visible_width, visible_height) = self.relative.visible_content_size;
+        let (visible_columns, visible_rows) = self.visible_cells();
+        let (content_width, content_height) = self.size();
+
+        if visible_width == 0 || visible_height == 0 {
+            return None;
+        }
+
+        let x = x.clamp(start_x, start_x + visible_width - 1);
+        let y = y.clamp(start_y, start_y + visible_height - 1);
+
+        // Only fails in a partially visible last column or row, which is after the last cell.
+        Some(self.apply_transform(x, y).unwrap_or((
+            min(self.offset.0 + visible_columns, content_width) - 1,
+            min(self.offset.1 + visible_rows, content_height) - 1,
+        )))
+    }
+
+    // Returns the visible terminal rectangle (x, y, width, height) covering content cells
+    // from (content_x1, content_y1) to (content_x2, content_y2) inclusive.
+    // Returns None if none of these cells are visible.
+    fn content_rect_to_screen(
+        &self,
+        content_x1: usize,
+        content_y1: usize,
+        content_x2: usize,
+        content_y2: usize,
+    ) -> Option<(u16, u16, u16, u16)> {
+        let (start_x, start_y) = self.relative.content_start;
+        let (visible_width, visible_height) = self.relative.visible_content_size;
+        let (cell_width, cell_height) = self.cell_size();
+        let (sub_columns, sub_rows) = self.sub_cells();
+        let (sub_columns, sub_rows) = (usize::from(sub_columns), usize::from(sub_rows));
+        let (offset_x, offset_y) = self.offset;
+
+        // Positions relative to content start, in terminal cells, clipped to visible content.
+        let x1 = content_x1.diff_or_zero(&offset_x) * usize::from(cell_width) / sub_columns;
+        let y1 = content_y1.diff_or_zero(&offset_y) * usize::from(cell_height) / sub_rows;
+        let x2 = min(
+            ((content_x2 + 1).diff_or_zero(&offset_x) * usize::from(cell_width))
+                .div_ceil(sub_columns),
+            usize::from(visible_width),
+        );
+        let y2 = min(
+            ((content_y2 + 1).diff_or_zero(&offset_y) * usize::from(cell_height))
+                .div_ceil(sub_rows),
+            usize::from(visible_height),
+        );
+
+        if x1 >= x2 || y1 >= y2 {
+            return None;
+        }
+
+        Some((
+            start_x + x1 as u16,
+            start_y + y1 as u16,
+            (x2 - x1) as u16,
+            (y2 - y1) as u16,
+        ))
+    }
+
+    // Returns what should be displayed at terminal cell (ix, iy), relative to content start.
+    fn screen_cell(&self, ix: u16, iy: u16) -> StyledContent<char> {
+        let cell = self.overlay_cell(ix, iy);
+
+        // Keyboard cursor is drawn on top of everything else, with reversed colors.
+        if self.cursor_at(ix, iy) {
+            cell.reverse()
+        } else {
+            cell
+        }
+    }
+
+    // Content at terminal cell (ix, iy) relative to content start, with grid drawn over it.
+    fn overlay_cell(&self, ix: u16, iy: u16) -> StyledContent<char> {
+        let cell = self.document.display_cell(
+            self.offset,
+            self.cell_size(),
+            usize::from(ix),
+            usize::from(iy),
+        );
+
+        // Grid is only an overlay, it's drawn on empty cells and never stored in content.
+        if let Cell::Painted(cell) = cell {
+            return cell;
+        }
+
+        if self.grid {
+            let (cell_width, cell_height) = self.cell_size();
+            let (sub_columns, sub_rows) = self.sub_cells();
+
+            if self
+                .grid_line_at(self.offset.0, cell_width, sub_columns, ix)
+                .is_some()
+                && self
+                    .grid_line_at(self.offset.1, cell_height, sub_rows, iy)
+                    .is_some()
+            {
+                return symbols::GRID_DOT.dark_grey();
+            }
+        }
+
+        cell.display()
+    }
+
+    // Whether terminal cell (ix, iy), relative to content start, shows the keyboard cursor.
+    fn cursor_at(&self, ix: u16, iy: u16) -> bool {
+        let (start_x, start_y) = self.relative.content_start;
+        let (x, y) = (start_x + ix, start_y + iy);
+
+        match self.cursor.and_then(|(cursor_x, cursor_y)| {
+            self.content_rect_to_screen(cursor_x, cursor_y, cursor_x, cursor_y)
+        }) {
+            Some((cursor_x, cursor_y, width, height)) => {
+                x >= cursor_x && y >= cursor_y && x < cursor_x + width && y < cursor_y + height
+            }
+            None => false,
+        }
+    }
+
+    // Returns the grid line (content column or row) that starts at terminal column or row {index},
+    // relative to content start. {offset}, {cell} and {sub} are the values for that axis.
+    fn grid_line_at(&self, offset: usize, cell: u16, sub: u16, index: u16) -> Option<usize> {
+        let (cell, sub, index) = (usize::from(cell), usize::from(sub), usize::from(index));
+
+        // Range of content cells that start inside this terminal cell.
+        let first = offset + (index * sub).div_ceil(cell);
+        let last = offset + ((index + 1) * sub - 1) / cell;
+
+        let line = first.div_ceil(self.grid_spacing) * self.grid_spacing;
+
+        if line <= last {
+            Some(line)
+        } else {
+            None
+        }
+    }
+
+    // Shows or hides grid and rulers.
+    pub fn toggle_grid(&mut self, painter: &mut Painter) -> crossterm::Result<()> {
+        self.set_grid(painter, !self.grid)
+    }
+
+    pub fn set_grid(&mut self, painter: &mut Painter, grid: bool) -> crossterm::Result<()> {
+        self.grid = grid;
+
+        self.draw_border(painter)?;
+        self.draw_content(painter)?;
+        painter.flush()
+    }
+
+    // Sets number of content cells between grid lines, at least one.
+    pub fn set_grid_spacing(
+        &mut self,
+        painter: &mut Painter,
+        spacing: usize,
+    ) -> crossterm::Result<()> {
+        self.grid_spacing = spacing.max(1);
+
+        // Rulers have to be removed from the border too.
+        self.redraw(painter)
+    }
+
+    fn draw_content(&self, painter: &mut Painter) -> crossterm::Result<()> {
+        let (start_x, start_y) = self.relative.content_start;
+        let (visible_width, visible_height) = self.relative.visible_content_size;
+
+        for iy in 0..visible_height {
+            // Move cursor to the start of each line.
+            painter.move_to(start_x, start_y + iy)?;
+
+            for ix in 0..visible_width {
+                // Write without moving cursor, because cursor is already in place.
+                painter.write_in_place(self.screen_cell(ix, iy))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Draws a single content cell at every terminal cell it covers.
+    fn draw_cell(
+        &self,
+        painter: &mut Painter,
+        content_x: usize,
+        content_y: usize,
+    ) -> crossterm::Result<()> {
+        let (start_x, start_y) = self.relative.content_start;
+
+        if let Some((x, y, width, height)) =
+            self.content_rect_to_screen(content_x, content_y, content_x, content_y)
+        {
+            for iy in (y - start_y)..(y - start_y + height) {
+                painter.move_to(x, start_y + iy)?;
+
+                for ix in (x - start_x)..(x - start_x + width) {
+                    painter.write_in_place(self.screen_cell(ix, iy))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw_border(&self, painter: &mut Painter) -> crossterm::Result<()> {
+        // TODO: redo this. It doesn't work well with small screen size,
+        // and it breaks the rule that each componenet shouldn't interact with outside its area.
+
+        let (start_x, start_y) = self.relative.content_start;
+        let (visible_width, visible_height) = self.relative.visible_content_size;
+
+        if start_x == 0 || start_y == 0 {
+            return Ok(());
+        }
+
+        painter.draw_box(
+            start_x - 1,
+            start_y - 1,
+            visible_width + 2,
+            visible_height + 2,
+        )?;
+
+        if self.grid {
+            self.draw_rulers(painter)?;
+        }
+
+        self.draw_scroll_indicators(painter)
+    }
+
+    // Draw grid line numbers, columns on the top border and rows (vertically) on the left border.
+    // Labels that would overlap the previous label are skipped.
+    fn draw_rulers(&self, painter: &mut Painter) -> crossterm::Result<()> {
+        let (start_x, start_y) = self.relative.content_start;
+        let (visible_width, visible_height) = self.relative.visible_content_size;
+        let (cell_width, cell_height) = self.cell_size();
+        let (sub_columns, sub_rows) = self.sub_cells();
+
+        painter.set_foreground(Color::DarkGrey)?;
+
+        let mut free_x = 0;
+
+        for ix in 0..visible_width {
+            if let Some(column) = self.grid_line_at(self.offset.0, cell_width, sub_columns, ix) {
+                let label = column.to_string();
+                let label_width = label.len() as u16;
+
+                if ix >= free_x && ix + label_width <= visible_width {
+                    painter.write(start_x + ix, start_y - 1, &label)?;
+                    free_x = ix + label_width + 1;
+                }
+            }
+        }
+
+        let mut free_y = 0;
+
+        for iy in 0..visible_height {
+            if let Some(row) = self.grid_line_at(self.offset.1, cell_height, sub_rows, iy) {
+                let label = row.to_string();
+                let label_height = label.len() as u16;
+
+                if iy >= free_y && iy + label_height <= visible_height {
+                    for (i, digit) in label.chars().enumerate() {
+                        painter.write(start_x - 1, start_y + iy + i as u16, digit)?;
+                    }
+                    free_y = iy + label_height + 1;
+                }
+            }
+        }
+
+        painter.set_foreground(Color::Reset)
+    }
+
+    // Draw arrows on the border in the directions where content is hidden.
+    fn draw_scroll_indicators(&self, painter: &mut Painter) -> crossterm::Result<()> {
+        let (start_x, start_y) = self.relative.content_start;
+        let (visible_width, visible_height) = self.relative.visible_content_size;
+        let (cell_width, cell_height) = self.cell_size();
+        let (offset_x, offset_y) = self.offset;
+        let (content_width, content_height) = self.size();
+
+        let middle_x = start_x + visible_width / 2;
+        let middle_y = start_y + visible_height / 2;
+
+        if offset_y > 0 {
+            painter.write(middle_x, start_y - 1, symbols::SCROLL_UP)?;
+        }
+
+        let (sub_columns, sub_rows) = self.sub_cells();
+        let visible_columns = usize::from(visible_width * sub_columns / cell_width);
+        let visible_rows = usize::from(visible_height * sub_rows / cell_height);
+
+        if offset_y + visible_rows < content_height {
+            painter.write(middle_x, start_y + visible_height, symbols::SCROLL_DOWN)?;
+        }
+
+        if offset_x > 0 {
+            painter.write(start_x - 1, middle_y, symbols::SCROLL_LEFT)?;
+        }
+
+        if offset_x + visible_columns < content_width {
+            painter.write(start_x + visible_width, middle_y, symbols::SCROLL_RIGHT)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn mouse_event(
+        &mut self,
+        event: MouseEvent,
+        painter: &mut Painter,
+        tool: &Tool,
+        brush: &StyledContent<char>,
+    ) -> crossterm::Result<()> {
+        let (click_x, click_y) = (event.column, event.row);
+        let brush = &self.document.brush(brush);
+        let tool = self.usable_tool(tool);
+
+        self.lower_half = event.modifiers.contains(KeyModifiers::ALT);
+        let position = self.apply_transform(click_x, click_y);
+
+        // Preview is only kept while the pointer moves, other events may change content under it.
+        if event.kind != MouseEventKind::Moved {
+            self.clear_hover(painter)?;
+        }
+
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some((content_x, content_y)) = position {
+                    self.click(painter, tool, brush, content_x, content_y)?;
+                }
+            }
+            MouseEventKind::Drag(MouseButton::Left) => {
+                // Shapes keep following the pointer when it leaves the viewport.
+                let position = if self.is_dragging() {
+                    self.clamped_transform(click_x, click_y)
+                } else {
+                    position
+                };
+
+                if let Some((content_x, content_y)) = position {
+                    self.drag(painter, tool, brush, content_x, content_y)?;
+                }
+            }
+            MouseEventKind::Moved => {
+                self.hover(painter, tool, brush, click_x, click_y)?;
+            }
+            MouseEventKind::Down(MouseButton::Middle) => {
+                self.pan_anchor = Some((click_x, click_y));
+            }
+            MouseEventKind::Drag(MouseButton::Middle) => {
+                if let Some((anchor_x, anchor_y)) = self.pan_anchor {
+                    let (cell_width, cell_height) = self.cell_size();
+                    let (sub_columns, sub_rows) = self.sub_cells();
+
+                    // Dragging moves the content with the pointer, so viewport moves the other way.
+                    let dx = ((anchor_x as i16) - (click_x as i16)) * (sub_columns as i16)
+                        / (cell_width as i16);
+                    let dy = ((anchor_y as i16) - (click_y as i16)) * (sub_rows as i16)
+                        / (cell_height as i16);
+
+                    // Keep the anchor until pointer moved at least one whole content cell.
+                    if dx != 0 || dy != 0 {
+                        self.pan_anchor = Some((click_x, click_y));
+                        self.pan(painter, dx, dy)?;
+                    }
+                }
+            }
+            MouseEventKind::Up(MouseButton::Middle) => {
+                self.pan_anchor = None;
+            }
+            MouseEventKind::ScrollDown => {
+                if event.modifiers.contains(KeyModifiers::CONTROL) {
+                    self.zoom(painter, -1, click_x, click_y)?;
+                } else if event.modifiers.contains(KeyModifiers::SHIFT) {
+                    self.pan(painter, 1, 0)?;
+                } else {
+                    self.pan(painter, 0, 1)?;
+                }
+            }
+            MouseEventKind::ScrollUp => {
+                if event.modifiers.contains(KeyModifiers::CONTROL) {
+                    self.zoom(painter, 1, click_x, click_y)?;
+                } else if event.modifiers.contains(KeyModifiers::SHIFT) {
+                    self.pan(painter, -1, 0)?;
+                } else {
+                    self.pan(painter, 0, -1)?;
+                }
+            }
+            _ => {}
+        }
 
-    // Empty character. Used for creating new Canvas and with Eraser tool.
-    fn empty() -> StyledContent<char> {
-        StyledContent::new(ContentStyle::default(), ' ')
+        Ok(())
     }
 
-    pub fn draw(&mut self, painter: &mut Painter, t_size: (u16, u16)) -> crossterm::Result<()> {
-        self.set_relative(t_size);
-
-        self.draw_border(painter)?;
+    // Tools that change content can't be used on hidden or locked layers.
+    pub fn can_use(&self, tool: &Tool) -> bool {
+        matches!(tool, Tool::Select | Tool::Move | Tool::ColorPicker) || self.editable()
+    }
 
-        self.draw_content(painter)
+    // Returns {tool} if it can be used, otherwise Select, which doesn't change content.
+    fn usable_tool<'a>(&self, tool: &'a Tool) -> &'a Tool {
+        if self.can_use(tool) {
+            tool
+        } else {
+            &Tool::Select
+        }
     }
 
-    fn set_relative(&mut self, t_size: (u16, u16)) {
-        let (x, y) = self.area.start.absolute_position(t_size);
-        let (width, height) = self.area.size(t_size);
-        let content_width = self.content[0].len();
-        let content_height = self.content.len();
+    // Whether a shape or selection is being dragged, which should get drag events from anywhere.
+    pub fn is_dragging(&self) -> bool {
+        self.dragged_rectangle().is_some()
+    }
 
-        let transform_x = (((content_width as i32) - (width as i32)) / 2) - (x as i32);
-        let transform_y = (((content_height as i32) - (height as i32)) / 2) - (y as i32);
+    // Drops what the tool was doing with the pointer.
+    fn cancel_drag(&mut self) {
+        self.active = None;
+        self.document.deselect();
+    }
 
-        let visible_content_width = min(content_width as u16, width);
-        let visible_content_height = min(content_height as u16, height);
+    // Content position of the keyboard cursor, None if keyboard drawing is off.
+    pub fn cursor(&self) -> Option<(usize, usize)> {
+        self.cursor
+    }
 
-        let content_start_x = if (content_width as u16) < width {
-            transform_x.unsigned_abs() as u16
-        } else {
-            x
-        };
+    // Turns keyboard drawing on or off. Cursor starts at the middle of the viewport.
+    pub fn toggle_cursor(&mut self, painter: &mut Painter) -> crossterm::Result<()> {
+        if self.cursor.take().is_none() {
+            let (visible_columns, visible_rows) = self.visible_cells();
+            let (content_width, content_height) = self.size();
+
+            self.cursor = Some((
+                min(self.offset.0 + visible_columns / 2, content_width - 1),
+                min(self.offset.1 + visible_rows / 2, content_height - 1),
+            ));
+        } else if self.cursor_pressed {
+            // Cursor was never released, drop what the tool was doing.
+            self.cancel_drag();
+        }
 
-        let content_start_y = if (content_height as u16) < height {
-            transform_y.unsigned_abs() as u16
-        } else {
-            y
-        };
+        self.cursor_pressed = false;
 
-        self.relative = Relative::new(
-            (transform_x, transform_y),
-            (visible_content_width, visible_content_height),
-            (content_start_x, content_start_y),
-        );
+        self.draw_content(painter)?;
+        painter.flush()
     }
 
-    // Transforms absolute position to content position.
-    // Returns None if parameters can't be converted.
-    // (If conversion result is less than 0, or bigger than content size).
-    fn apply_transform(&self, x: u16, y: u16) -> Option<(usize, usize)> {
-        let content_width = self.content[0].len();
-        let content_height = self.content.len();
-        let (transform_x, transform_y) = self.relative.transform;
-
-        let result_x = i32::from(x) + transform_x;
-        let result_y = i32::from(y) + transform_y;
-
-        // If transformed position is negative or bigger than content size, return None.
-        if result_x < 0
-            || result_x >= content_width as i32
-            || result_y < 0
-            || result_y >= content_height as i32
-        {
-            return None;
+    // Keyboard equivalent of pressing the left mouse button at the cursor.
+    pub fn press_cursor(
+        &mut self,
+        painter: &mut Painter,
+        tool: &Tool,
+        brush: &StyledContent<char>,
+    ) -> crossterm::Result<()> {
+        // Holding the key repeats it, only the first one is a press.
+        if self.cursor_pressed {
+            return Ok(());
         }
 
-        Some((result_x as usize, result_y as usize))
-    }
-
-    fn draw_content(&self, painter: &mut Painter) -> crossterm::Result<()> {
-        let (start_x, start_y) = self.relative.content_start;
-        let (visible_width, visible_height) = self.relative.visible_content_size;
+        if let Some((content_x, content_y)) = self.cursor {
+            let brush = &self.document.brush(brush);
+            let tool = self.usable_tool(tool);
 
-        if let Some((content_x, content_y)) = self.apply_transform(start_x, start_y) {
-            for iy in 0..visible_height {
-                // Write first character in each line.
-                painter.write(
-                    start_x,
-                    start_y + iy,
-                    self.content[content_y + usize::from(iy)][content_x],
-                )?;
-
-                // Start from 1 because first character is already written.
-                for ix in 1..visible_width {
-                    // Write without moving cursor, because cursor is already in place.
-                    painter.write_in_place(
-                        self.content[content_y + usize::from(iy)][content_x + usize::from(ix)],
-                    )?;
-                }
-            }
+            self.clear_hover(painter)?;
+            self.cursor_pressed = true;
+            self.click(painter, tool, brush, content_x, content_y)?;
         }
 
-        Ok(())
+        painter.flush()
     }
 
-    fn draw_border(&self, painter: &mut Painter) -> crossterm::Result<()> {
-        // TODO: redo this. It doesn't work well with small screen size,
-        // and it breaks the rule that each componenet shouldn't interact with outside its area.
-
-        let (start_x, start_y) = self.relative.content_start;
-        let (visible_width, visible_height) = self.relative.visible_content_size;
-
-        if start_x == 0 || start_y == 0 {
+    // Keyboard equivalent of releasing the left mouse button.
+    pub fn release_cursor(
+        &mut self,
+        painter: &mut Painter,
+        tool: &Tool,
+        brush: &StyledContent<char>,
+    ) -> crossterm::Result<()> {
+        if !self.cursor_pressed {
             return Ok(());
         }
 
-        painter.draw_box(
-            start_x - 1,
-            start_y - 1,
-            visible_width + 2,
-            visible_height + 2,
-        )
+        self.cursor_pressed = false;
+        self.release(painter, tool, brush)?;
+        painter.flush()
     }
 
-    pub fn mouse_event(
+    // Moves the keyboard cursor by (dx, dy) content cells, panning to keep it visible.
+    // If the cursor is pressed, this is a drag of {tool} to the new position.
+    pub fn move_cursor(
         &mut self,
-        event: MouseEvent,
         painter: &mut Painter,
         tool: &Tool,
         brush: &StyledContent<char>,
+        dx: i16,
+        dy: i16,
     ) -> crossterm::Result<()> {
-        let (click_x, click_y) = (event.column, event.row);
-
-        match event.kind {
-            MouseEventKind::Down(MouseButton::Left) => {
-                self.click(painter, tool, brush, click_x, click_y)?;
+        if let Some((x, y)) = self.cursor {
+            let (content_width, content_height) = self.size();
+            let (new_x, new_y) = (
+                min(x.add_sub_or_zero(&dx), content_width - 1),
+                min(y.add_sub_or_zero(&dy), content_height - 1),
+            );
+
+            if (new_x, new_y) == (x, y) {
+                return Ok(());
             }
-            MouseEventKind::Drag(MouseButton::Left) => {
-                self.drag(painter, tool, brush, click_x, click_y)?;
+
+            self.cursor = Some((new_x, new_y));
+
+            // Old cell is drawn again without the cursor.
+            self.draw_cell(painter, x, y)?;
+            self.scroll_to_cursor(painter)?;
+            self.draw_cell(painter, new_x, new_y)?;
+
+            if self.cursor_pressed {
+                let brush = &self.document.brush(brush);
+                let tool = self.usable_tool(tool);
+
+                self.drag(painter, tool, brush, new_x, new_y)?;
             }
-            _ => {}
+        }
+
+        painter.flush()
+    }
+
+    // Pans the viewport, if needed, so the keyboard cursor is visible.
+    fn scroll_to_cursor(&mut self, painter: &mut Painter) -> crossterm::Result<()> {
+        if let Some((x, y)) = self.cursor {
+            let (visible_columns, visible_rows) = self.visible_cells();
+            let distance = |position: usize, offset: usize, visible: usize| {
+                if position < offset {
+                    -((offset - position) as i16)
+                } else if position >= offset + visible {
+                    (position + 1 - offset - visible) as i16
+                } else {
+                    0
+                }
+            };
+
+            let dx = distance(x, self.offset.0, visible_columns);
+            let dy = distance(y, self.offset.1, visible_rows);
+
+            self.pan(painter, dx, dy)?;
         }
 
         Ok(())
     }
 
+    // Number of whole content columns and rows that are visible in the viewport.
+    fn visible_cells(&self) -> (usize, usize) {
+        let (visible_width, visible_height) = self.relative.visible_content_size;
+        let (cell_width, cell_height) = self.cell_size();
+        let (sub_columns, sub_rows) = self.sub_cells();
+
+        (
+            usize::from(visible_width * sub_columns / cell_width).max(1),
+            usize::from(visible_height * sub_rows / cell_height).max(1),
+        )
+    }
+
     fn click(
         &mut self,
         painter: &mut Painter,
         tool: &Tool,
-        brush: &StyledContent<char>,
-        click_x: u16,
-        click_y: u16,
+        brush: &Cell,
+        content_x: usize,
+        content_y: usize,
     ) -> crossterm::Result<()> {
-        if let Some((content_x, content_y)) = self.apply_transform(click_x, click_y) {
-            match tool {
-                Tool::Select => {
-                    self.select_click(painter, click_x, click_y)?;
-                }
-                Tool::Move => {}
-                Tool::Rectangle => {
-                    self.select_click(painter, click_x, click_y)?;
-                }
-                Tool::Circle => {}
-                Tool::Brush => {
-                    self.brush(painter, click_x, click_y, content_x, content_y, brush)?
-                }
-                Tool::Erase => self.brush(
-                    painter,
-                    click_x,
-                    click_y,
-                    content_x,
-                    content_y,
-                    &Self::empty(),
-                )?,
-                Tool::Bucket => {
-                    self.bucket(painter, click_x, click_y, content_x, content_y, brush)?
-                }
-                Tool::ColorPicker => {}
-                Tool::Text => {}
+        match tool {
+            Tool::Select => {
+                self.select_click(painter, content_x, content_y)?;
             }
+            Tool::Move => {}
+            Tool::Rectangle | Tool::Line | Tool::Circle => {
+                self.shape_click(painter, content_x, content_y)?;
+            }
+            Tool::Brush => {
+                self.active = None;
+                self.brush(painter, content_x, content_y, brush)?
+            }
+            Tool::Erase => {
+                self.active = None;
+                self.brush(painter, content_x, content_y, &Cell::Empty)?
+            }
+            Tool::Bucket => self.bucket(painter, content_x, content_y, brush)?,
+            Tool::ColorPicker => {}
+            Tool::Text => {}
         }
 
         Ok(())
@@ -210,35 +1116,26 @@ impl Canvas {
         &mut self,
         painter: &mut Painter,
         tool: &Tool,
-        brush: &StyledContent<char>,
-        click_x: u16,
-        click_y: u16,
+        brush: &Cell,
+        content_x: usize,
+        content_y: usize,
     ) -> crossterm::Result<()> {
-        if let Some((content_x, content_y)) = self.apply_transform(click_x, click_y) {
-            match tool {
-                Tool::Select => {
-                    self.select_drag(painter, click_x, click_y)?;
-                }
-                Tool::Move => {}
-                Tool::Rectangle => {
-                    self.select_drag(painter, click_x, click_y)?;
-                }
-                Tool::Circle => {}
-                Tool::Brush => {
-                    self.brush(painter, click_x, click_y, content_x, content_y, brush)?
-                }
-                Tool::Erase => self.brush(
-                    painter,
-                    click_x,
-                    click_y,
-                    content_x,
-                    content_y,
-                    &Self::empty(),
-                )?,
-                Tool::Bucket => {}
-                Tool::ColorPicker => {}
-                Tool::Text => {}
+        match tool {
+            Tool::Select => {
+                self.select_drag(painter, content_x, content_y)?;
+            }
+            Tool::Move => {}
+            Tool::Rectangle | Tool::Circle => {
+                self.shape_drag(painter, content_x, content_y)?;
             }
+            Tool::Line => {
+                self.line_drag(painter, content_x, content_y, brush)?;
+            }
+            Tool::Brush => self.brush(painter, content_x, content_y, brush)?,
+            Tool::Erase => self.brush(painter, content_x, content_y, &Cell::Empty)?,
+            Tool::Bucket => {}
+            Tool::ColorPicker => {}
+            Tool::Text => {}
         }
 
         Ok(())
@@ -250,12 +1147,34 @@ impl Canvas {
         tool: &Tool,
         brush: &StyledContent<char>,
     ) -> crossterm::Result<()> {
+        let brush = self.document.brush(brush);
+
+        if !self.can_use(tool) {
+            self.cancel_drag();
+            return Ok(());
+        }
+
         match tool {
             Tool::Select => {
                 self.select_release(painter)?;
             }
             Tool::Rectangle => {
-                self.rectangle_release(painter, brush)?;
+                self.shape_release(painter, |document, selection| {
+                    document.rectangle(selection, brush, true)
+                })?;
+            }
+            Tool::Circle => {
+                self.shape_release(painter, |document, selection| {
+                    document.ellipse(selection, brush, false)
+                })?;
+            }
+            Tool::Line => {
+                self.shape_release(painter, |document, selection| {
+                    document.line(selection.start, selection.end, brush)
+                })?;
+            }
+            Tool::Brush | Tool::Erase => {
+                self.active = None;
             }
             _ => {}
         }
@@ -266,10 +1185,12 @@ impl Canvas {
     fn select_click(
         &mut self,
         painter: &mut Painter,
-        click_x: u16,
-        click_y: u16,
+        content_x: usize,
+        content_y: usize,
     ) -> crossterm::Result<()> {
-        self.active = Some(Active::new(click_x, click_y));
+        let position = (content_x, content_y);
+
+        self.document.select(Selection::new(position, position));
 
         self.draw_select(painter)
     }
@@ -277,11 +1198,12 @@ impl Canvas {
     fn select_drag(
         &mut self,
         painter: &mut Painter,
-        click_x: u16,
-        click_y: u16,
+        content_x: usize,
+        content_y: usize,
     ) -> crossterm::Result<()> {
-        if let Some(active) = &mut self.active {
-            active.update(click_x, click_y);
+        if let Some(selection) = self.document.selection() {
+            self.document
+                .select(Selection::new(selection.start, (content_x, content_y)));
 
             return self.draw_select(painter);
         }
@@ -290,188 +1212,343 @@ impl Canvas {
     }
 
     fn select_release(&mut self, painter: &mut Painter) -> crossterm::Result<()> {
-        self.active = None;
+        self.document.deselect();
         self.draw_content(painter)?;
         painter.flush()
     }
 
+    // Draws content with a dashed box around the rectangle being selected or dragged.
     fn draw_select(&mut self, painter: &mut Painter) -> crossterm::Result<()> {
-        if let Some(active) = &self.active {
-            let (start_x, start_y) = active.start_position;
-            let (last_x, last_y) = active.last_position;
-
-            let x = min(start_x, last_x);
-            let y = min(start_y, last_y);
-            let width = start_x.abs_diff(last_x) + 1;
-            let height = start_y.abs_diff(last_y) + 1;
+        if let Some(rectangle) = self.dragged_rectangle() {
+            let ((x1, y1), (x2, y2)) = rectangle.bounds();
 
             self.draw_content(painter)?;
-            painter.draw_dashed_box(x, y, width, height)?;
+
+            if let Some((x, y, width, height)) = self.content_rect_to_screen(x1, y1, x2, y2) {
+                painter.draw_dashed_box(x, y, width, height)?;
+            }
+
             return painter.flush();
         }
 
         Ok(())
     }
 
-    fn rectangle_release(
+    // Starts dragging a shape from (content_x, content_y).
+    fn shape_click(
         &mut self,
         painter: &mut Painter,
-        brush: &StyledContent<char>,
+        content_x: usize,
+        content_y: usize,
     ) -> crossterm::Result<()> {
-        if let Some(active) = &self.active {
-            let (start_x, start_y) = active.start_position;
-            let (last_x, last_y) = active.last_position;
+        let position = (content_x, content_y);
 
-            let x = min(start_x, last_x);
-            let y = min(start_y, last_y);
-            let width = start_x.abs_diff(last_x) + 1;
-            let height = start_y.abs_diff(last_y) + 1;
+        self.active = Some(Selection::new(position, position));
 
-            self.active = None;
+        self.draw_select(painter)
+    }
 
-            if let Some((content_x, content_y)) = self.apply_transform(x, y) {
-                for iy in content_y..(content_y + usize::from(height)) {
-                    for ix in content_x..(content_x + usize::from(width)) {
-                        self.content[iy][ix] = *brush;
-                    }
-                }
+    fn shape_drag(
+        &mut self,
+        painter: &mut Painter,
+        content_x: usize,
+        content_y: usize,
+    ) -> crossterm::Result<()> {
+        if let Some(active) = &mut self.active {
+            active.end = (content_x, content_y);
 
-                self.draw_content(painter)?;
-                return painter.flush();
-            }
+            return self.draw_select(painter);
         }
 
         Ok(())
     }
 
-    fn brush(
+    // Finishes dragging a shape, {draw} paints it between the drag start and end.
+    fn shape_release(
         &mut self,
         painter: &mut Painter,
-        click_x: u16,
-        click_y: u16,
-        content_x: usize,
-        content_y: usize,
-        brush: &StyledContent<char>,
+        draw: impl FnOnce(&mut Document, Selection),
     ) -> crossterm::Result<()> {
-        self.content[content_y][content_x] = *brush;
+        if let Some(active) = self.active.take() {
+            self.checkpoint();
+            draw(&mut self.document, active);
+            self.mark_changed();
 
-        painter.write(click_x, click_y, brush)?;
-        painter.flush()?;
+            self.draw_content(painter)?;
+            return painter.flush();
+        }
 
         Ok(())
     }
 
-    fn bucket(
+    // Draws a preview of what {tool} would paint under the pointer, without changing content.
+    fn hover(
         &mut self,
         painter: &mut Painter,
-        click_x: u16,
-        click_y: u16,
-        content_x: usize,
-        content_y: usize,
-        brush: &StyledContent<char>,
+        tool: &Tool,
+        brush: &Cell,
+        x: u16,
+        y: u16,
     ) -> crossterm::Result<()> {
-        let selected = self.content[content_y][content_x];
+        let position = self.apply_transform(x, y);
 
-        self.apply_bucket(
-            painter, click_x, click_y, content_x, content_y, brush, &selected,
-        )?;
+        if position == self.hover {
+            return Ok(());
+        }
 
-        self.draw_content(painter)?;
-        painter.flush()?;
+        self.clear_hover(painter)?;
+
+        let preview = match tool {
+            Tool::Rectangle | Tool::Line | Tool::Circle | Tool::Brush | Tool::Bucket => *brush,
+            Tool::Erase => Cell::Empty,
+            _ => return painter.flush(),
+        };
+
+        if let Some((content_x, content_y)) = position {
+            // Draw the preview from content, then restore the real cell.
+            let previous = self.active_cell(content_x, content_y);
+
+            self.document.set(content_x, content_y, preview);
+            self.draw_cell(painter, content_x, content_y)?;
+            self.document.set(content_x, content_y, previous);
+
+            self.hover = position;
+        }
+
+        painter.flush()
+    }
+
+    // Cell at (content_x, content_y) of the active layer.
+    fn active_cell(&self, content_x: usize, content_y: usize) -> Cell {
+        self.document
+            .layer_cell(self.document.active_layer(), content_x, content_y)
+    }
+
+    // Removes brush preview by drawing the cell under it again from content.
+    pub fn clear_hover(&mut self, painter: &mut Painter) -> crossterm::Result<()> {
+        if let Some((content_x, content_y)) = self.hover.take() {
+            self.draw_cell(painter, content_x, content_y)?;
+        }
 
         Ok(())
     }
 
-    fn apply_bucket(
+    fn line_drag(
         &mut self,
         painter: &mut Painter,
-        x: u16,
-        y: u16,
         content_x: usize,
         content_y: usize,
-        brush: &StyledContent<char>,
-        selected: &StyledContent<char>,
+        brush: &Cell,
     ) -> crossterm::Result<()> {
-        // Check that we're still inside canvas, if not return.
-        if content_x >= self.content[0].len() || content_y >= self.content.len() {
-            return Ok(());
-        }
+        if let Some(active) = &mut self.active {
+            active.end = (content_x, content_y);
 
-        let current = self.content[content_y][content_x];
+            // Preview the line by drawing it into content, then restoring the old cells.
+            let points = shape::line(active.start, active.end);
+            let previous: Vec<Cell> = points
+                .iter()
+                .map(|&(x, y)| self.active_cell(x, y))
+                .collect();
 
-        if &current != selected || &current == brush {
-            return Ok(());
-        }
+            for &(x, y) in &points {
+                self.document.set(x, y, *brush);
+            }
 
-        self.content[content_y][content_x] = *brush;
+            self.draw_content(painter)?;
 
-        let adjacent: [(i16, i16); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+            for (&(x, y), cell) in points.iter().zip(previous) {
+                self.document.set(x, y, cell);
+            }
 
-        // Spread.
-        for add in adjacent {
-            self.apply_bucket(
-                painter,
-                x.add_sub_or_zero(&add.0),
-                y.add_sub_or_zero(&add.1),
-                content_x.add_sub_or_zero(&add.0),
-                content_y.add_sub_or_zero(&add.1),
-                brush,
-                selected,
-            )?;
+            return painter.flush();
         }
 
         Ok(())
     }
-}
 
-struct Active {
-    start_position: (u16, u16),
-    last_position: (u16, u16),
-}
+    fn brush(
+        &mut self,
+        painter: &mut Painter,
+        content_x: usize,
+        content_y: usize,
+        brush: &Cell,
+    ) -> crossterm::Result<()> {
+        let position = (content_x, content_y);
+
+        // A whole stroke is undone at once.
+        if self.active.is_none() {
+            self.checkpoint();
+        }
+
+        // Connect to the previous position of this stroke, because pointer can move
+        // more than one content cell between events (especially in sub cell modes).
+        let start = self.active.take().map_or(position, |active| active.end);
 
-impl Active {
-    fn new(start_x: u16, start_y: u16) -> Active {
-        Active {
-            start_position: (start_x, start_y),
-            last_position: (start_x, start_y),
+        for (x, y) in shape::line(start, position) {
+            self.document.set(x, y, *brush);
+            self.draw_cell(painter, x, y)?;
         }
+
+        self.active = Some(Selection::new(position, position));
+        self.mark_changed();
+        painter.flush()?;
+
+        Ok(())
     }
 
-    fn update(&mut self, last_x: u16, last_y: u16) {
-        self.last_position = (last_x, last_y)
+    fn bucket(
+        &mut self,
+        painter: &mut Painter,
+        content_x: usize,
+        content_y: usize,
+        brush: &Cell,
+    ) -> crossterm::Result<()> {
+        self.checkpoint();
+        self.document.flood_fill(content_x, content_y, *brush);
+        self.mark_changed();
+
+        self.draw_content(painter)?;
+        painter.flush()?;
+
+        Ok(())
     }
 }
 
 // Fields that depends on terminal window size.
 struct Relative {
-    // Transforms absolute position to its corresponding content position.
-    transform: (i32, i32),
-
-    // Visible content width and height.
+    // Visible content width and height, in terminal cells.
     visible_content_size: (u16, u16),
 
     // Absolute position of content starting point.
     content_start: (u16, u16),
+
+    // Terminal size these fields were calculated for.
+    t_size: (u16, u16),
 }
 
 impl Relative {
     fn zero() -> Relative {
         Relative {
-            transform: (0, 0),
             visible_content_size: (0, 0),
             content_start: (0, 0),
+            t_size: (0, 0),
         }
     }
 
     fn new(
-        transform: (i32, i32),
         visible_content_size: (u16, u16),
         content_start: (u16, u16),
+        t_size: (u16, u16),
     ) -> Relative {
         Relative {
-            transform,
             visible_content_size,
             content_start,
+            t_size,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::{
+        event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
+        style::Stylize,
+    };
+
+    use super::*;
+    use crate::{
+        app::area::{Corner, Point},
+        painter::memory::MemoryBackend,
+    };
+
+    fn mouse(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    // 8x5 canvas that fills the screen, inside its border.
+    fn canvas() -> (Canvas, Painter, MemoryBackend) {
+        let backend = MemoryBackend::new(10, 7);
+        let mut painter = Painter::new(Box::new(backend.clone()));
+        let mut canvas = Canvas::new(
+            Area::new(
+                Point::new(1, 1, Corner::TopLeft),
+                Point::new(1, 1, Corner::BottomRight),
+            ),
+            8,
+            5,
+        );
+
+        canvas.draw(&mut painter, (10, 7)).unwrap();
+        painter.flush().unwrap();
+
+        (canvas, painter, backend)
+    }
+
+    // Drags the left button from (x1, y1) to (x2, y2) with {tool}.
+    fn drag(
+        canvas: &mut Canvas,
+        painter: &mut Painter,
+        tool: Tool,
+        (x1, y1): (u16, u16),
+        (x2, y2): (u16, u16),
+    ) {
+        let brush = 'X'.cyan();
+        let events = [
+            mouse(MouseEventKind::Down(MouseButton::Left), x1, y1),
+            mouse(MouseEventKind::Drag(MouseButton::Left), x2, y2),
+        ];
+
+        for event in events {
+            canvas.mouse_event(event, painter, &tool, &brush).unwrap();
         }
+
+        canvas.release(painter, &tool, &brush).unwrap();
+    }
+
+    #[test]
+    fn brush_paints_screen() {
+        let (mut canvas, mut painter, backend) = canvas();
+
+        assert_eq!(backend.line(0), "┌────────┐");
+        assert_eq!(backend.line(1), "│        │");
+
+        drag(&mut canvas, &mut painter, Tool::Brush, (2, 2), (4, 2));
+
+        assert_eq!(backend.line(2), "│ XXX    │");
+        assert_eq!(backend.cell(3, 2), Some('X'.cyan()));
+        assert!(canvas.is_dirty());
+    }
+
+    #[test]
+    fn rectangle_fills_dragged_area() {
+        let (mut canvas, mut painter, backend) = canvas();
+
+        drag(&mut canvas, &mut painter, Tool::Rectangle, (2, 2), (5, 4));
+
+        assert_eq!(backend.line(1), "│        │");
+        assert_eq!(backend.line(2), "│ XXXX   │");
+        assert_eq!(backend.line(4), "│ XXXX   │");
+        assert_eq!(backend.line(5), "│        │");
+        assert_eq!(canvas.document().export(), "\n XXXX\n XXXX\n XXXX\n\n");
+    }
+
+    #[test]
+    fn circle_draws_ellipse_outline() {
+        let (mut canvas, mut painter, backend) = canvas();
+
+        drag(&mut canvas, &mut painter, Tool::Circle, (2, 2), (7, 5));
+
+        assert_eq!(backend.line(2), "│  XXXX  │");
+        assert_eq!(backend.line(3), "│ X    X │");
+        assert_eq!(
+            canvas.document().export(),
+            "\n  XXXX\n X    X\n X    X\n  XXXX\n"
+        );
+        assert_eq!(canvas.selection_size(), None);
     }
 }
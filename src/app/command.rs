@@ -0,0 +1,467 @@
+use std::fs;
+
+use crossterm::{
+    event::{KeyCode, KeyEvent},
+    style::{ContentStyle, Stylize},
+};
+
+use crate::{painter::Painter, utils::DiffOrZero};
+
+use super::{
+    area::Area,
+    input::Input,
+    panel::{Tool, TOOLS},
+};
+
+// Command names, used for completion.
+const COMMANDS: [&str; 10] = [
+    "w", "e", "e!", "import", "import!", "resize", "fill", "clear", "tool", "set",
+];
+
+// Options of :set, and the values they accept (empty if it's a number).
+const OPTIONS: [(&str, &[&str]); 2] = [("grid", &["on", "off"]), ("gridspacing", &[])];
+
+// Biggest content width or height accepted by :resize.
+const MAX_SIZE: usize = 1000;
+
+// Columns always left for the input when an error is shown.
+const MIN_INPUT_WIDTH: u16 = 10;
+
+pub enum Command {
+    // Export to path, or to the last export path if None.
+    Write(Option<String>),
+    // Open a drawing, in the format for its extension. {force} discards unsaved changes.
+    Edit { path: String, force: bool },
+    // Ask for options to convert an image into the canvas, {force} discards unsaved changes.
+    Import { path: String, force: bool },
+    Resize(usize, usize),
+    // Fill active layer with the brush.
+    Fill,
+    // Empty the active layer.
+    Clear,
+    Tool(Tool),
+    Grid(bool),
+    GridSpacing(usize),
+}
+
+impl Command {
+    // Parses command line {text} (without ':'), returns a message if it's not a valid command.
+    pub fn parse(text: &str) -> Result<Command, String> {
+        let text = text.trim();
+        let (name, rest) = match text.split_once(char::is_whitespace) {
+            Some((name, rest)) => (name, rest.trim()),
+            None => (text, ""),
+        };
+        let arguments: Vec<&str> = rest.split_whitespace().collect();
+
+        let command = match (name, arguments.as_slice()) {
+            // Paths are the rest of the line, so they can have spaces.
+            ("w", []) => Command::Write(None),
+            ("w", _) => Command::Write(Some(rest.to_string())),
+            ("e" | "e!", []) => return Err(String::from("Expected a file path")),
+            ("e" | "e!", _) => Command::Edit {
+                path: rest.to_string(),
+                force: name == "e!",
+            },
+            ("import" | "import!", []) => return Err(String::from("Expected an image path")),
+            ("import" | "import!", _) => Command::Import {
+                path: rest.to_string(),
+                force: name == "import!",
+            },
+            ("resize", [width, height]) => Command::Resize(size(width)?, size(height)?),
+            ("resize", _) => return Err(String::from("Expected width and height")),
+            ("fill", []) => Command::Fill,
+            ("clear", []) => Command::Clear,
+            ("tool", [tool]) => TOOLS
+                .into_iter()
+                .find(|other| other.config_name() == *tool)
+                .map(Command::Tool)
+                .ok_or_else(|| format!("Unknown tool: {}", tool))?,
+            ("tool", _) => return Err(String::from("Expected a tool name")),
+            ("set", ["grid", "on"]) => Command::Grid(true),
+            ("set", ["grid", "off"]) => Command::Grid(false),
+            ("set", ["gridspacing", spacing]) => Command::GridSpacing(size(spacing)?),
+            ("set", _) => return Err(String::from("Expected grid on|off, or gridspacing N")),
+            (name, _) if COMMANDS.contains(&name) => {
+                return Err(format!("{} doesn't take arguments", name))
+            }
+            (name, _) => return Err(format!("Unknown command: {}", name)),
+        };
+
+        Ok(command)
+    }
+}
+
+// Parses a width, height or spacing.
+fn size(text: &str) -> Result<usize, String> {
+    match text.parse() {
+        Ok(size @ 1..=MAX_SIZE) => Ok(size),
+        _ => Err(format!(
+            "Expected a number from 1 to {}, found {}",
+            MAX_SIZE, text
+        )),
+    }
+}
+
+// Returns where the word being completed starts in {text} (text before the cursor),
+// and everything that can replace it.
+fn completions(text: &str) -> (usize, Vec<String>) {
+    let (name, rest) = match text.split_once(' ') {
+        Some((name, rest)) => (name, rest.trim_start()),
+        None => return (0, matching(&COMMANDS, text)),
+    };
+
+    // Start of rest in text.
+    let rest_start = text.len() - rest.len();
+
+    if matches!(name, "w" | "e" | "e!" | "import" | "import!") {
+        return (rest_start, path_completions(rest));
+    }
+
+    let (start, word) = match rest.rsplit_once(' ') {
+        Some((_, word)) => (text.len() - word.len(), word),
+        None => (rest_start, rest),
+    };
+    let arguments: Vec<&str> = text[..start].split_whitespace().skip(1).collect();
+
+    let candidates = match (name, arguments.as_slice()) {
+        ("tool", []) => {
+            let tools: Vec<String> = TOOLS.iter().map(Tool::config_name).collect();
+            let tools: Vec<&str> = tools.iter().map(String::as_str).collect();
+
+            matching(&tools, word)
+        }
+        ("set", []) => {
+            let options: Vec<&str> = OPTIONS.iter().map(|(option, _)| *option).collect();
+
+            matching(&options, word)
+        }
+        ("set", [option]) => OPTIONS
+            .iter()
+            .find(|(other, _)| other == option)
+            .map_or(Vec::new(), |(_, values)| matching(values, word)),
+        _ => Vec::new(),
+    };
+
+    (start, candidates)
+}
+
+// Candidates that start with {prefix}.
+fn matching(candidates: &[&str], prefix: &str) -> Vec<String> {
+    candidates
+        .iter()
+        .filter(|candidate| candidate.starts_with(prefix))
+        .map(|candidate| candidate.to_string())
+        .collect()
+}
+
+// Files and directories (ending with '/') that start with {path}, sorted.
+// Hidden ones are only included if {path} file name starts with '.'.
+fn path_completions(path: &str) -> Vec<String> {
+    let (dir, prefix) = match path.rfind('/') {
+        Some(index) => path.split_at(index + 1),
+        None => ("", path),
+    };
+
+    let Ok(entries) = fs::read_dir(if dir.is_empty() { "." } else { dir }) else {
+        return Vec::new();
+    };
+
+    let mut paths: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+
+            let slash = if entry.path().is_dir() { "/" } else { "" };
+
+            Some(format!("{}{}{}", dir, name, slash))
+        })
+        .collect();
+
+    paths.sort();
+
+    paths
+}
+
+// Tab completion in progress, repeated Tab goes through candidates.
+struct Completion {
+    // Where the completed word starts in text before the cursor.
+    start: usize,
+    candidates: Vec<String>,
+    // Index in candidates used by the next Tab.
+    next: usize,
+}
+
+// Line at the bottom of the screen where commands are typed after ':', drawn instead of
+// the status bar while it's open.
+pub struct CommandLine {
+    pub area: Area,
+    // None if command line is closed.
+    input: Option<Input>,
+    // Why the last command failed, shown after the input until it's edited.
+    error: Option<String>,
+    completion: Option<Completion>,
+}
+
+impl CommandLine {
+    pub fn new(area: Area) -> CommandLine {
+        CommandLine {
+            area,
+            input: None,
+            error: None,
+            completion: None,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.input.is_some()
+    }
+
+    pub fn open(&mut self) {
+        self.input = Some(Input::new(String::new()));
+        self.error = None;
+        self.completion = None;
+    }
+
+    pub fn close(&mut self) {
+        self.input = None;
+    }
+
+    // Shows {error}, command line stays open so the command can be fixed.
+    pub fn set_error(&mut self, error: String) {
+        self.error = Some(error);
+    }
+
+    pub fn draw(&self, painter: &mut Painter, t_size: (u16, u16)) -> crossterm::Result<()> {
+        let (x, y) = self.area.start.absolute_position(t_size);
+        let (width, height) = self.area.size(t_size);
+
+        let Some(input) = &self.input else {
+            return Ok(());
+        };
+
+        if width < 2 || height == 0 {
+            return Ok(());
+        }
+
+        painter.fill(x, y, width, 1, ' ')?;
+        painter.write(x, y, ':')?;
+
+        // Error is shown at the end of the line, cut to leave some space for the input.
+        let max_error_width = usize::from((width - 1).diff_or_zero(&MIN_INPUT_WIDTH));
+        let error: Option<String> = self.error.as_ref().map(|error| {
+            format!(" {} ", error)
+                .chars()
+                .take(max_error_width)
+                .collect()
+        });
+        let error_width = error
+            .as_ref()
+            .map_or(0, |error| error.chars().count() as u16);
+        let input_width = (width - 1) - error_width;
+
+        input.draw(
+            painter,
+            x + 1,
+            y,
+            usize::from(input_width),
+            ContentStyle::new(),
+        )?;
+
+        if let Some(error) = error {
+            painter.write(x + 1 + input_width, y, error.white().on_red())?;
+        }
+
+        Ok(())
+    }
+
+    // Handles a key while the command line is open.
+    // Returns the command text when Enter is pressed, the command line stays open.
+    pub fn key_event(&mut self, event: KeyEvent) -> Option<String> {
+        let input = self.input.as_mut()?;
+
+        if event.code != KeyCode::Tab {
+            self.completion = None;
+        }
+
+        match event.code {
+            KeyCode::Enter => return Some(input.text()),
+            KeyCode::Esc => self.close(),
+            KeyCode::Backspace if input.is_empty() => self.close(),
+            KeyCode::Tab => self.complete(),
+            code => {
+                if input.key_event(code, event.modifiers) {
+                    self.error = None;
+                }
+            }
+        }
+
+        None
+    }
+
+    // Replaces the word before the cursor with the next completion candidate.
+    fn complete(&mut self) {
+        let Some(input) = &mut self.input else {
+            return;
+        };
+
+        let before = input.before_cursor();
+        let mut completion = self.completion.take().unwrap_or_else(|| {
+            let (start, candidates) = completions(&before);
+
+            Completion {
+                start,
+                candidates,
+                next: 0,
+            }
+        });
+
+        if let Some(candidate) = completion.candidates.get(completion.next) {
+            input.replace_before_cursor(&format!("{}{}", &before[..completion.start], candidate));
+            self.error = None;
+        }
+
+        // With only one candidate, next Tab starts a new completion (e.g. inside a directory).
+        if completion.candidates.len() > 1 {
+            completion.next = (completion.next + 1) % completion.candidates.len();
+            self.completion = Some(completion);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use crossterm::event::KeyModifiers;
+
+    use super::{
+        super::area::{Corner, Point},
+        *,
+    };
+
+    fn error(text: &str) -> String {
+        Command::parse(text).err().unwrap_or_default()
+    }
+
+    #[test]
+    fn parse_arguments() {
+        assert!(matches!(Command::parse(" w "), Ok(Command::Write(None))));
+        assert!(matches!(
+            Command::parse("w my drawing.ans"),
+            Ok(Command::Write(Some(path))) if path == "my drawing.ans"
+        ));
+        assert!(matches!(
+            Command::parse("e a.txt"),
+            Ok(Command::Edit { path, force: false }) if path == "a.txt"
+        ));
+        assert!(matches!(
+            Command::parse("e! a.txt"),
+            Ok(Command::Edit { force: true, .. })
+        ));
+        assert!(matches!(
+            Command::parse("set grid on"),
+            Ok(Command::Grid(true))
+        ));
+
+        assert_eq!(error("e"), "Expected a file path");
+        assert_eq!(error("import!"), "Expected an image path");
+        assert_eq!(error("resize 4"), "Expected width and height");
+        assert_eq!(error("fill now"), "fill doesn't take arguments");
+        assert_eq!(error("tool pen"), "Unknown tool: pen");
+        assert_eq!(error("bogus"), "Unknown command: bogus");
+    }
+
+    #[test]
+    fn parse_size_bounds() {
+        assert!(matches!(
+            Command::parse("resize 1 1000"),
+            Ok(Command::Resize(1, 1000))
+        ));
+        assert_eq!(
+            error("resize 0 5"),
+            "Expected a number from 1 to 1000, found 0"
+        );
+        assert_eq!(
+            error("resize 5 1001"),
+            "Expected a number from 1 to 1000, found 1001"
+        );
+        assert_eq!(
+            error("set gridspacing -2"),
+            "Expected a number from 1 to 1000, found -2"
+        );
+    }
+
+    #[test]
+    fn completion_start_and_candidates() {
+        assert_eq!(
+            completions("im"),
+            (0, vec![String::from("import"), String::from("import!")])
+        );
+        assert_eq!(completions("tool li"), (5, vec![String::from("line")]));
+        assert_eq!(
+            completions("set grid o"),
+            (9, vec![String::from("on"), String::from("off")])
+        );
+        assert_eq!(completions("set  gridspacing "), (17, Vec::new()));
+        assert_eq!(completions("fill x"), (5, Vec::new()));
+    }
+
+    #[test]
+    fn path_completion() {
+        let dir = env::temp_dir().join(format!("paint-tui-{}-completion", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("drawings")).unwrap();
+        fs::write(dir.join("drawing.ans"), "").unwrap();
+        fs::write(dir.join(".hidden"), "").unwrap();
+
+        let path = format!("{}/", dir.display());
+
+        assert_eq!(
+            path_completions(&format!("{}dr", path)),
+            [format!("{}drawing.ans", path), format!("{}drawings/", path)]
+        );
+        assert_eq!(path_completions(&path).len(), 2);
+        assert_eq!(
+            path_completions(&format!("{}.", path)),
+            [format!("{}.hidden", path)]
+        );
+
+        // Paths start after the command, even with spaces before them.
+        let text = format!("e  {}dr", path);
+
+        assert_eq!(completions(&text).0, 3);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn tab_cycles_through_candidates() {
+        let mut command_line = CommandLine::new(Area::new(
+            Point::new(0, 0, Corner::BottomLeft),
+            Point::new(0, 0, Corner::BottomRight),
+        ));
+        let mut texts = Vec::new();
+
+        command_line.open();
+
+        for code in "set g".chars().map(KeyCode::Char).chain([KeyCode::Tab; 3]) {
+            command_line.key_event(KeyEvent::new(code, KeyModifiers::NONE));
+        }
+
+        for code in [KeyCode::Tab, KeyCode::Char(' '), KeyCode::Tab] {
+            command_line.key_event(KeyEvent::new(code, KeyModifiers::NONE));
+            texts.push(command_line.input.as_ref().unwrap().text());
+        }
+
+        // Three Tabs went from grid to gridspacing and back, the fourth is gridspacing again.
+        assert_eq!(
+            texts,
+            ["set gridspacing", "set gridspacing ", "set gridspacing "]
+        );
+    }
+}
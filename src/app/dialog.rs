@@ -0,0 +1,391 @@
+use std::cmp::{max, min};
+
+use crossterm::{
+    event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind},
+    style::{Attribute, ContentStyle, Stylize},
+};
+
+use crate::{painter::Painter, utils::DiffOrZero};
+
+use super::input::Input;
+
+// Smallest width of a dialog, dialogs grow to fit their message and buttons.
+const MIN_WIDTH: u16 = 40;
+
+// Space between buttons.
+const BUTTON_GAP: u16 = 2;
+
+// How a dialog was closed.
+pub enum DialogResult {
+    // Button at this index was chosen, with the text in the input (empty if there is no input).
+    Button(usize, String),
+    // Closed with Esc.
+    Cancel,
+}
+
+// Modal dialog with a title, a message (lines are centered), an optional text input and a row of
+// buttons.
+// Drawn in the middle of the screen over everything else, and gets all events while it's open.
+// Whoever opens it has to draw the screen again when it's closed.
+pub struct Dialog {
+    title: String,
+    message: String,
+    input: Option<Input>,
+    buttons: Vec<String>,
+    // Index in buttons of the button Enter chooses.
+    selected: usize,
+    relative: Relative, // Fields that depends on terminal window size.
+}
+
+impl Dialog {
+    // There has to be at least one button, so the dialog can be closed with Enter.
+    pub fn new<S: Into<String>>(title: S, message: S, buttons: &[&str]) -> Dialog {
+        assert!(!buttons.is_empty(), "dialog without buttons");
+
+        Dialog {
+            title: title.into(),
+            message: message.into(),
+            input: None,
+            buttons: buttons.iter().map(|button| button.to_string()).collect(),
+            selected: 0,
+            relative: Relative::zero(),
+        }
+    }
+
+    // Dialog with a text input, starting with {text}.
+    pub fn prompt<S: Into<String>>(title: S, message: S, text: S, buttons: &[&str]) -> Dialog {
+        Dialog {
+            input: Some(Input::new(text.into())),
+            ..Dialog::new(title, message, buttons)
+        }
+    }
+
+    pub fn draw(&mut self, painter: &mut Painter, t_size: (u16, u16)) -> crossterm::Result<()> {
+        self.set_relative(t_size);
+
+        self.draw_dialog(painter)
+    }
+
+    fn set_relative(&mut self, t_size: (u16, u16)) {
+        let (t_width, t_height) = t_size;
+        let (width, height) = self.full_size();
+        let size = (min(width, t_width), min(height, t_height));
+        let start = (
+            t_width.diff_or_zero(&size.0) / 2,
+            t_height.diff_or_zero(&size.1) / 2,
+        );
+
+        self.relative = Relative::new(start, size);
+    }
+
+    // Size needed to show everything.
+    fn full_size(&self) -> (u16, u16) {
+        let message_width = self
+            .message
+            .lines()
+            .map(|line| line.chars().count() as u16)
+            .max()
+            .unwrap_or(0);
+        let width = max(MIN_WIDTH, max(message_width, self.buttons_width()) + 4);
+
+        // Borders, empty rows around message and buttons, and input with an empty row after it.
+        let height = self.message_height() + if self.input.is_some() { 8 } else { 6 };
+
+        (width, height)
+    }
+
+    fn message_height(&self) -> u16 {
+        self.message.lines().count().max(1) as u16
+    }
+
+    // Whether the whole dialog fits in the terminal window.
+    fn fits(&self) -> bool {
+        self.relative.size == self.full_size()
+    }
+
+    fn input_row(&self) -> u16 {
+        self.relative.start.1 + self.message_height() + 3
+    }
+
+    fn buttons_row(&self) -> u16 {
+        let (_, height) = self.relative.size;
+
+        self.relative.start.1 + height - 3
+    }
+
+    // Input starts after the border and one empty column, and has the same space after it.
+    fn input_width(&self) -> usize {
+        usize::from(self.relative.size.0.diff_or_zero(&4))
+    }
+
+    fn draw_dialog(&self, painter: &mut Painter) -> crossterm::Result<()> {
+        let (x, y) = self.relative.start;
+        let (width, height) = self.relative.size;
+
+        // Only a line saying so, buttons can't be used until they can be seen.
+        if !self.fits() {
+            let notice: String = "Window too small, Esc cancels"
+                .chars()
+                .take(usize::from(width))
+                .collect();
+
+            painter.fill(x, y, width, height, ' ')?;
+            painter.write(x, y + height / 2, &notice)?;
+
+            return painter.flush();
+        }
+
+        painter.fill(x, y, width, height, ' ')?;
+        painter.draw_box(x, y, width, height)?;
+        painter.write(x + 2, y, &self.title)?;
+
+        for (line_y, line) in (y + 2..).zip(self.message.lines()) {
+            painter.write(x + (width - line.chars().count() as u16) / 2, line_y, line)?;
+        }
+
+        if let Some(input) = &self.input {
+            input.draw(
+                painter,
+                x + 2,
+                self.input_row(),
+                self.input_width(),
+                ContentStyle::new().underlined(),
+            )?;
+        }
+
+        for (index, (button_x, button)) in self.button_positions().zip(&self.buttons).enumerate() {
+            if index == self.selected {
+                painter.set_attribute(Attribute::Reverse)?;
+            }
+
+            painter.write(button_x, self.buttons_row(), button)?;
+
+            if index == self.selected {
+                painter.set_attribute(Attribute::Reset)?;
+            }
+        }
+
+        painter.flush()
+    }
+
+    fn buttons_width(&self) -> u16 {
+        let labels: usize = self
+            .buttons
+            .iter()
+            .map(|button| button.chars().count())
+            .sum();
+
+        labels as u16 + BUTTON_GAP * (self.buttons.len() as u16).diff_or_zero(&1)
+    }
+
+    // Absolute x position of each button, buttons are centered in one row.
+    fn button_positions(&self) -> impl Iterator<Item = u16> + '_ {
+        let (x, _) = self.relative.start;
+        let (width, _) = self.relative.size;
+        let mut button_x = x + width.diff_or_zero(&self.buttons_width()) / 2;
+
+        self.buttons.iter().map(move |button| {
+            let position = button_x;
+            button_x += button.chars().count() as u16 + BUTTON_GAP;
+            position
+        })
+    }
+
+    // Result of choosing the button at {index}.
+    fn choose(&self, index: usize) -> DialogResult {
+        let text = match &self.input {
+            Some(input) => input.text(),
+            None => String::new(),
+        };
+
+        DialogResult::Button(index, text)
+    }
+
+    // Returns how the dialog was closed with this key, None if it's still open.
+    pub fn key_event(
+        &mut self,
+        event: KeyEvent,
+        painter: &mut Painter,
+    ) -> crossterm::Result<Option<DialogResult>> {
+        let total_buttons = self.buttons.len();
+
+        // Hidden buttons aren't chosen by accident, cancelling is always safe.
+        if !self.fits() {
+            return Ok((event.code == KeyCode::Esc).then_some(DialogResult::Cancel));
+        }
+
+        match (&mut self.input, event.code) {
+            (_, KeyCode::Esc) => return Ok(Some(DialogResult::Cancel)),
+            (_, KeyCode::Enter) => return Ok(Some(self.choose(self.selected))),
+            (_, KeyCode::Tab) => self.selected = (self.selected + 1) % total_buttons,
+            (_, KeyCode::BackTab) => {
+                self.selected = (self.selected + total_buttons - 1) % total_buttons;
+            }
+            (Some(input), code) => {
+                if !input.key_event(code, event.modifiers) {
+                    return Ok(None);
+                }
+            }
+            // Without an input, arrows select buttons, and a button's first letter chooses it.
+            (None, KeyCode::Left) => self.selected = self.selected.diff_or_zero(&1),
+            (None, KeyCode::Right) => self.selected = min(self.selected + 1, total_buttons - 1),
+            (None, KeyCode::Char(key)) => {
+                let shortcut = |button: &String| {
+                    button
+                        .chars()
+                        .find(|character| character.is_alphanumeric())
+                        .map(|character| character.to_ascii_lowercase())
+                };
+
+                return Ok(self
+                    .buttons
+                    .iter()
+                    .position(|button| shortcut(button) == Some(key.to_ascii_lowercase()))
+                    .map(|index| self.choose(index)));
+            }
+            _ => return Ok(None),
+        }
+
+        self.draw_dialog(painter)?;
+
+        Ok(None)
+    }
+
+    // Returns how the dialog was closed with this mouse event, None if it's still open.
+    pub fn mouse_event(
+        &mut self,
+        event: MouseEvent,
+        painter: &mut Painter,
+    ) -> crossterm::Result<Option<DialogResult>> {
+        let (x, _) = self.relative.start;
+        let (click_x, click_y) = (event.column, event.row);
+
+        if !self.fits() || event.kind != MouseEventKind::Down(MouseButton::Left) {
+            return Ok(None);
+        }
+
+        if click_y == self.buttons_row() {
+            let clicked =
+                self.button_positions()
+                    .zip(&self.buttons)
+                    .position(|(button_x, button)| {
+                        click_x >= button_x && click_x < button_x + button.chars().count() as u16
+                    });
+
+            return Ok(clicked.map(|index| self.choose(index)));
+        }
+
+        let width = self.input_width();
+
+        if click_y == self.input_row() && click_x >= x + 2 {
+            if let Some(input) = &mut self.input {
+                input.click(usize::from(click_x - x - 2), width);
+                self.draw_dialog(painter)?;
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+// Fields that depends on terminal window size.
+struct Relative {
+    start: (u16, u16),
+    size: (u16, u16),
+}
+
+impl Relative {
+    fn zero() -> Relative {
+        Relative {
+            start: (0, 0),
+            size: (0, 0),
+        }
+    }
+
+    fn new(start: (u16, u16), size: (u16, u16)) -> Relative {
+        Relative { start, size }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyModifiers;
+
+    use crate::painter::memory::MemoryBackend;
+
+    use super::*;
+
+    // Presses {code} in {dialog}, returns the result if it was closed.
+    fn press(dialog: &mut Dialog, painter: &mut Painter, code: KeyCode) -> Option<DialogResult> {
+        dialog
+            .key_event(KeyEvent::new(code, KeyModifiers::NONE), painter)
+            .unwrap()
+    }
+
+    fn chosen(result: Option<DialogResult>) -> Option<(usize, String)> {
+        match result {
+            Some(DialogResult::Button(index, text)) => Some((index, text)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn keys_cycle_and_choose_buttons() {
+        let mut painter = Painter::new(Box::new(MemoryBackend::new(80, 24)));
+        let mut dialog = Dialog::new("Title", "Message", &["[ Yes ]", "[ No ]", "[ Maybe ]"]);
+
+        dialog.draw(&mut painter, (80, 24)).unwrap();
+
+        // Tab wraps around to the first button, BackTab back to the last one.
+        for code in [KeyCode::Tab, KeyCode::Tab, KeyCode::Tab] {
+            assert!(press(&mut dialog, &mut painter, code).is_none());
+        }
+
+        assert_eq!(dialog.selected, 0);
+        assert!(press(&mut dialog, &mut painter, KeyCode::BackTab).is_none());
+        assert_eq!(dialog.selected, 2);
+
+        // Arrows stop at the ends.
+        press(&mut dialog, &mut painter, KeyCode::Right);
+
+        assert_eq!(dialog.selected, 2);
+
+        press(&mut dialog, &mut painter, KeyCode::Left);
+
+        assert_eq!(
+            chosen(press(&mut dialog, &mut painter, KeyCode::Enter)),
+            Some((1, String::new()))
+        );
+        assert_eq!(
+            chosen(press(&mut dialog, &mut painter, KeyCode::Char('M'))),
+            Some((2, String::new()))
+        );
+        assert!(matches!(
+            press(&mut dialog, &mut painter, KeyCode::Esc),
+            Some(DialogResult::Cancel)
+        ));
+    }
+
+    #[test]
+    fn enter_chooses_with_input_text() {
+        let mut painter = Painter::new(Box::new(MemoryBackend::new(80, 24)));
+        let mut dialog = Dialog::prompt("Title", "Message", "ab", &["[ Ok ]", "[ Cancel ]"]);
+
+        dialog.draw(&mut painter, (80, 24)).unwrap();
+
+        // Letters go to the input instead of choosing buttons.
+        press(&mut dialog, &mut painter, KeyCode::Char('c'));
+        press(&mut dialog, &mut painter, KeyCode::Tab);
+
+        assert_eq!(
+            chosen(press(&mut dialog, &mut painter, KeyCode::Enter)),
+            Some((1, String::from("abc")))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "dialog without buttons")]
+    fn dialog_needs_a_button() {
+        Dialog::new("Title", "Message", &[]);
+    }
+}
@@ -0,0 +1,116 @@
+use std::cmp::min;
+
+use crossterm::{
+    event::{KeyCode, KeyModifiers},
+    style::{ContentStyle, StyledContent, Stylize},
+};
+
+use crate::{painter::Painter, utils::DiffOrZero};
+
+// Single line text input, used by dialogs and the command line.
+pub struct Input {
+    text: Vec<char>,
+    // Index in text where typed characters are inserted, can be text length (after the end).
+    cursor: usize,
+}
+
+impl Input {
+    pub fn new(text: String) -> Input {
+        let text: Vec<char> = text.chars().collect();
+
+        Input {
+            cursor: text.len(),
+            text,
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.text.iter().collect()
+    }
+
+    // Text before the cursor.
+    pub fn before_cursor(&self) -> String {
+        self.text[..self.cursor].iter().collect()
+    }
+
+    // Replaces text before the cursor with {text}, keeping what is after it.
+    pub fn replace_before_cursor(&mut self, text: &str) {
+        let after = self.text.split_off(self.cursor);
+
+        self.text = text.chars().collect();
+        self.cursor = self.text.len();
+        self.text.extend(after);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    // First visible character when only {width} characters fit, so the cursor is always visible.
+    fn scroll(&self, width: usize) -> usize {
+        (self.cursor + 1).diff_or_zero(&width)
+    }
+
+    // Moves cursor to visible {column}, when only {width} characters fit.
+    pub fn click(&mut self, column: usize, width: usize) {
+        if column < width {
+            self.cursor = min(self.scroll(width) + column, self.text.len());
+        }
+    }
+
+    // Edits text or moves cursor, returns false if the key isn't used by the input.
+    pub fn key_event(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        match code {
+            KeyCode::Char('u') if modifiers.contains(KeyModifiers::CONTROL) => {
+                self.text.drain(..self.cursor);
+                self.cursor = 0;
+            }
+            KeyCode::Char(_) if modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Char(character) => {
+                self.text.insert(self.cursor, character);
+                self.cursor += 1;
+            }
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.text.remove(self.cursor);
+            }
+            KeyCode::Delete if self.cursor < self.text.len() => {
+                self.text.remove(self.cursor);
+            }
+            KeyCode::Left => self.cursor = self.cursor.diff_or_zero(&1),
+            KeyCode::Right => self.cursor = min(self.cursor + 1, self.text.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.len(),
+            _ => return false,
+        }
+
+        true
+    }
+
+    // Draws the visible part of the text in {width} cells from (x, y), with the cursor in reverse.
+    // Empty cells after the text are drawn too, so old text is removed.
+    pub fn draw(
+        &self,
+        painter: &mut Painter,
+        x: u16,
+        y: u16,
+        width: usize,
+        style: ContentStyle,
+    ) -> crossterm::Result<()> {
+        let scroll = self.scroll(width);
+
+        painter.move_to(x, y)?;
+
+        for index in scroll..(scroll + width) {
+            let character = StyledContent::new(style, self.text.get(index).copied().unwrap_or(' '));
+
+            if index == self.cursor {
+                painter.write_in_place(character.reverse())?;
+            } else {
+                painter.write_in_place(character)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,428 @@
+use std::{collections::HashMap, fs, io::ErrorKind, path::Path};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use super::panel::{Tool, TOOLS};
+
+// Config file name, inside the paint-tui config directory.
+pub const CONFIG_FILE: &str = "config.ini";
+
+// What a key can be bound to.
+#[derive(Clone, Copy)]
+pub enum Action {
+    Quit,
+    Tool(Tool),
+    ZoomIn,
+    ZoomOut,
+    // Moves the viewport by (dx, dy) content cells.
+    Pan(i16, i16),
+    ToggleMode,
+    ToggleGrid,
+    Export,
+    // Turns keyboard drawing on or off.
+    ToggleKeyboard,
+    // Moves the keyboard cursor by (dx, dy) content cells.
+    Cursor(i16, i16),
+    Press,
+    Release,
+    // Opens the command line.
+    Command,
+    // Makes the mouse target the next content cell inside terminal cells.
+    MouseTarget,
+    Undo,
+    // Exports to the last export path, without asking for it.
+    Save,
+}
+
+impl Action {
+    // Action from its name in the config file.
+    fn parse(name: &str) -> Option<Action> {
+        let action = match name {
+            "quit" => Action::Quit,
+            "zoom-in" => Action::ZoomIn,
+            "zoom-out" => Action::ZoomOut,
+            "pan-left" => Action::Pan(-1, 0),
+            "pan-right" => Action::Pan(1, 0),
+            "pan-up" => Action::Pan(0, -1),
+            "pan-down" => Action::Pan(0, 1),
+            "mode" => Action::ToggleMode,
+            "grid" => Action::ToggleGrid,
+            "export" => Action::Export,
+            "keyboard" => Action::ToggleKeyboard,
+            "cursor-left" => Action::Cursor(-1, 0),
+            "cursor-right" => Action::Cursor(1, 0),
+            "cursor-up" => Action::Cursor(0, -1),
+            "cursor-down" => Action::Cursor(0, 1),
+            "press" => Action::Press,
+            "release" => Action::Release,
+            "command" => Action::Command,
+            "mouse-target" => Action::MouseTarget,
+            "undo" => Action::Undo,
+            "save" => Action::Save,
+            _ => {
+                let tool = name.strip_prefix("tool.")?;
+
+                return TOOLS
+                    .into_iter()
+                    .find(|other| other.config_name() == tool)
+                    .map(Action::Tool);
+            }
+        };
+
+        Some(action)
+    }
+}
+
+// A key with modifiers, e.g. ctrl+s.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> KeyChord {
+        // Shift is already part of the character (e.g. 'A' or '+'),
+        // and terminals don't agree on whether they report it as a modifier too.
+        let modifiers = match code {
+            KeyCode::Char(_) => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+
+        KeyChord { code, modifiers }
+    }
+
+    fn key(code: KeyCode) -> KeyChord {
+        KeyChord::new(code, KeyModifiers::NONE)
+    }
+
+    // Parses chords like "q", "ctrl+s", "shift+left", "f2" or "+".
+    fn parse(chord: &str) -> Option<KeyChord> {
+        // '+' separates modifiers, so it can only be the last part when it's the key.
+        let (modifiers, key) = match chord.strip_suffix('+') {
+            Some(modifiers) => (modifiers.strip_suffix('+').unwrap_or(modifiers), "+"),
+            None => chord.rsplit_once('+').unwrap_or(("", chord)),
+        };
+
+        let mut shift = false;
+        let mut result = KeyModifiers::NONE;
+
+        for modifier in modifiers.split('+').filter(|modifier| !modifier.is_empty()) {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" => result |= KeyModifiers::CONTROL,
+                "alt" => result |= KeyModifiers::ALT,
+                "shift" => shift = true,
+                _ => return None,
+            }
+        }
+
+        let mut characters = key.chars();
+
+        let code = match (characters.next(), characters.next()) {
+            (Some(character), None) if shift => KeyCode::Char(character.to_ascii_uppercase()),
+            (Some(character), None) => KeyCode::Char(character),
+            _ => match key.to_lowercase().as_str() {
+                "space" => KeyCode::Char(' '),
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "delete" => KeyCode::Delete,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').map(str::parse) {
+                    Some(Ok(number @ 1..=12)) => KeyCode::F(number),
+                    _ => return None,
+                },
+            },
+        };
+
+        if shift && !matches!(code, KeyCode::Char(_)) {
+            result |= KeyModifiers::SHIFT;
+        }
+
+        Some(KeyChord::new(code, result))
+    }
+}
+
+// Maps keys to actions. Built-in bindings can be changed from the config file:
+//
+//   # Comments start with '#' or ';'.
+//   [keys]
+//   ctrl+q = quit
+//   q = none          # removes a binding
+//   "=" = zoom-in     # quotes are optional, but needed for '#' and ';'
+//
+//   [cursor]          # only used in keyboard drawing, before [keys]
+//   w = cursor-up     # hides the tool bound to the same key in [keys], like h/j/k/l do
+//   l = none          # gives the key back to [keys]
+//
+// Actions are quit, undo, save (exports without asking for a path), export, zoom-in, zoom-out,
+// pan-left/right/up/down, mode, grid, keyboard, command, mouse-target, tool.NAME (e.g.
+// tool.line), and in [cursor] also cursor-left/right/up/down, press and release.
+// There is no palette action, brush colors aren't picked from a palette.
+pub struct Keymap {
+    keys: HashMap<KeyChord, Action>,
+    cursor: HashMap<KeyChord, Action>,
+}
+
+impl Keymap {
+    // Built-in bindings.
+    pub fn new() -> Keymap {
+        let mut keys = HashMap::from([
+            (KeyChord::key(KeyCode::Char('q')), Action::Quit),
+            (KeyChord::key(KeyCode::Left), Action::Pan(-1, 0)),
+            (KeyChord::key(KeyCode::Right), Action::Pan(1, 0)),
+            (KeyChord::key(KeyCode::Up), Action::Pan(0, -1)),
+            (KeyChord::key(KeyCode::Down), Action::Pan(0, 1)),
+            (KeyChord::key(KeyCode::Char('+')), Action::ZoomIn),
+            (KeyChord::key(KeyCode::Char('=')), Action::ZoomIn),
+            (KeyChord::key(KeyCode::Char('-')), Action::ZoomOut),
+            (KeyChord::key(KeyCode::F(2)), Action::ToggleMode),
+            (KeyChord::key(KeyCode::Char('g')), Action::ToggleGrid),
+            (KeyChord::key(KeyCode::F(3)), Action::Export),
+            (KeyChord::key(KeyCode::F(4)), Action::ToggleKeyboard),
+            (KeyChord::key(KeyCode::F(5)), Action::MouseTarget),
+            (
+                KeyChord::new(KeyCode::Char('z'), KeyModifiers::CONTROL),
+                Action::Undo,
+            ),
+            (
+                KeyChord::new(KeyCode::Char('s'), KeyModifiers::CONTROL),
+                Action::Save,
+            ),
+            (KeyChord::key(KeyCode::Char(':')), Action::Command),
+        ]);
+
+        for tool in TOOLS {
+            let shortcut = tool.shortcut();
+
+            keys.insert(KeyChord::key(KeyCode::Char(shortcut)), Action::Tool(tool));
+            keys.insert(
+                KeyChord::key(KeyCode::Char(shortcut.to_ascii_uppercase())),
+                Action::Tool(tool),
+            );
+        }
+
+        // In keyboard drawing h/j/k/l hide the Line and Bucket shortcuts, L and K still work.
+        let cursor = HashMap::from([
+            (KeyChord::key(KeyCode::Left), Action::Cursor(-1, 0)),
+            (KeyChord::key(KeyCode::Right), Action::Cursor(1, 0)),
+            (KeyChord::key(KeyCode::Up), Action::Cursor(0, -1)),
+            (KeyChord::key(KeyCode::Down), Action::Cursor(0, 1)),
+            (KeyChord::key(KeyCode::Char('h')), Action::Cursor(-1, 0)),
+            (KeyChord::key(KeyCode::Char('l')), Action::Cursor(1, 0)),
+            (KeyChord::key(KeyCode::Char('k')), Action::Cursor(0, -1)),
+            (KeyChord::key(KeyCode::Char('j')), Action::Cursor(0, 1)),
+            (KeyChord::key(KeyCode::Char(' ')), Action::Press),
+            (KeyChord::key(KeyCode::Enter), Action::Release),
+        ]);
+
+        Keymap { keys, cursor }
+    }
+
+    // Built-in bindings changed by the config file in {dir}, if it exists.
+    // Returns the keymap, and a message for each invalid line in the config file.
+    pub fn load(dir: Option<&Path>) -> (Keymap, Vec<String>) {
+        let mut keymap = Keymap::new();
+
+        let Some(path) = dir.map(|dir| dir.join(CONFIG_FILE)) else {
+            return (keymap, Vec::new());
+        };
+
+        let errors = match fs::read_to_string(&path) {
+            Ok(text) => keymap
+                .apply(&text)
+                .into_iter()
+                .map(|error| format!("{}:{}", path.display(), error))
+                .collect(),
+            Err(error) if error.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(error) => vec![format!("{}: {}", path.display(), error)],
+        };
+
+        (keymap, errors)
+    }
+
+    // Applies config file {text}, returns errors as "line: message".
+    fn apply(&mut self, text: &str) -> Vec<String> {
+        let mut errors = Vec::new();
+        // Bindings before any section header go to [keys]. None after an unknown section.
+        let mut section = Some(&mut self.keys);
+
+        for (index, line) in text.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            let mut error = |message: String| errors.push(format!("{}: {}", index + 1, message));
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                section = match name.trim() {
+                    "keys" => Some(&mut self.keys),
+                    "cursor" => Some(&mut self.cursor),
+                    name => {
+                        error(format!("unknown section [{}]", name));
+                        None
+                    }
+                };
+                continue;
+            }
+
+            // Action names don't have '=', so the last one is the separator (for "= = zoom-in").
+            let Some((chord, action)) = line.rsplit_once('=') else {
+                error(format!("expected \"key = action\", found \"{}\"", line));
+                continue;
+            };
+
+            let (chord, action) = (unquote(chord.trim()), unquote(action.trim()));
+
+            let Some(bindings) = section.as_deref_mut() else {
+                continue;
+            };
+
+            let Some(chord) = KeyChord::parse(chord) else {
+                error(format!("unknown key \"{}\"", chord));
+                continue;
+            };
+
+            if action == "none" {
+                bindings.remove(&chord);
+            } else if let Some(action) = Action::parse(action) {
+                bindings.insert(chord, action);
+            } else {
+                error(format!("unknown action \"{}\"", action));
+            }
+        }
+
+        errors
+    }
+
+    // Action bound to the key in {event}. Bindings in [cursor] are only used in {keyboard} drawing.
+    pub fn action(&self, event: KeyEvent, keyboard: bool) -> Option<Action> {
+        let chord = KeyChord::new(event.code, event.modifiers);
+
+        keyboard
+            .then(|| self.cursor.get(&chord))
+            .flatten()
+            .or_else(|| self.keys.get(&chord))
+            .copied()
+    }
+}
+
+// {line} without the comment at its end, which starts with '#' or ';' outside double quotes.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+
+    for (index, character) in line.char_indices() {
+        match character {
+            '"' => quoted = !quoted,
+            '#' | ';' if !quoted => return &line[..index],
+            _ => {}
+        }
+    }
+
+    line
+}
+
+// Removes quotes around {text}, if there are any.
+fn unquote(text: &str) -> &str {
+    text.strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+        .unwrap_or(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Action bound to {character} without modifiers.
+    fn action(keymap: &Keymap, character: char) -> Option<Action> {
+        keymap.action(
+            KeyEvent::new(KeyCode::Char(character), KeyModifiers::NONE),
+            false,
+        )
+    }
+
+    #[test]
+    fn chords() {
+        let chord = |text, code, modifiers| {
+            assert!(
+                KeyChord::parse(text) == Some(KeyChord::new(code, modifiers)),
+                "{}",
+                text
+            );
+        };
+
+        chord("ctrl+s", KeyCode::Char('s'), KeyModifiers::CONTROL);
+        chord(
+            "Ctrl+Alt+F2",
+            KeyCode::F(2),
+            KeyModifiers::CONTROL | KeyModifiers::ALT,
+        );
+        chord("shift+left", KeyCode::Left, KeyModifiers::SHIFT);
+        chord("shift+a", KeyCode::Char('A'), KeyModifiers::NONE);
+        chord("+", KeyCode::Char('+'), KeyModifiers::NONE);
+        chord("ctrl++", KeyCode::Char('+'), KeyModifiers::CONTROL);
+        chord("space", KeyCode::Char(' '), KeyModifiers::NONE);
+
+        assert!(KeyChord::parse("f13").is_none());
+        assert!(KeyChord::parse("super+a").is_none());
+    }
+
+    #[test]
+    fn quotes_and_comments() {
+        let mut keymap = Keymap::new();
+        let errors = keymap.apply(
+            "# comment\n\
+             ; comment\n\
+             x = quit # a=b\n\
+             \"#\" = zoom-in ; \"=\" = zoom-out\n\
+             = = tool.line\n\
+             u = undo\n\
+             w = save\n\
+             q = none\n",
+        );
+
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(matches!(action(&keymap, 'x'), Some(Action::Quit)));
+        assert!(matches!(action(&keymap, '#'), Some(Action::ZoomIn)));
+        assert!(matches!(action(&keymap, '='), Some(Action::Tool(_))));
+        assert!(matches!(action(&keymap, 'u'), Some(Action::Undo)));
+        assert!(matches!(action(&keymap, 'w'), Some(Action::Save)));
+        assert!(action(&keymap, 'q').is_none());
+    }
+
+    #[test]
+    fn errors_have_line_numbers() {
+        let mut keymap = Keymap::new();
+        let errors = keymap.apply(
+            "x = quit\n\
+             [mouse]\n\
+             y = bogus\n\
+             [keys]\n\
+             nothing here\n\
+             hyper+z = quit\n\
+             z = bogus # comment\n",
+        );
+
+        assert_eq!(
+            errors,
+            [
+                "2: unknown section [mouse]",
+                "5: expected \"key = action\", found \"nothing here\"",
+                "6: unknown key \"hyper+z\"",
+                "7: unknown action \"bogus\"",
+            ]
+        );
+    }
+}
@@ -0,0 +1,231 @@
+use std::cmp::min;
+
+use crossterm::{
+    event::{MouseButton, MouseEvent, MouseEventKind},
+    style::Attribute,
+};
+
+use paint_tui::Layer;
+
+use crate::{constant::symbols, painter::Painter, utils::DiffOrZero};
+
+use super::area::Area;
+
+// Changes to layers requested from LayerPanel, applied by Canvas.
+pub enum LayerAction {
+    Select(usize),
+    ToggleVisible(usize),
+    ToggleLock(usize),
+    Add,
+    Delete,
+    MoveUp,
+    MoveDown,
+}
+
+// Buttons in the bottom row of the panel, with their offset from the panel start.
+const BUTTONS: [(u16, &str); 4] = [
+    (1, symbols::ADD_LAYER),
+    (5, symbols::DELETE_LAYER),
+    (9, symbols::LAYER_UP),
+    (13, symbols::LAYER_DOWN),
+];
+
+// Lists layers, top layer first, with buttons to change them.
+pub struct LayerPanel {
+    pub area: Area,
+    relative: Relative, // Fields that depends on terminal window size.
+}
+
+impl LayerPanel {
+    pub fn new(area: Area) -> LayerPanel {
+        LayerPanel {
+            area,
+            relative: Relative::zero(),
+        }
+    }
+
+    pub fn draw(
+        &mut self,
+        painter: &mut Painter,
+        t_size: (u16, u16),
+        layers: &[Layer],
+        active_layer: usize,
+    ) -> crossterm::Result<()> {
+        self.set_relative(t_size, layers.len());
+
+        self.draw_panel(painter, layers, active_layer)
+    }
+
+    fn set_relative(&mut self, t_size: (u16, u16), total_layers: usize) {
+        let (width, height) = self.area.size(t_size);
+
+        // Top and bottom border, and buttons row.
+        let visible_layers = min(usize::from(height.diff_or_zero(&3)), total_layers);
+        let panel_start = self.area.start.absolute_position(t_size);
+
+        // Same as RightPanel, keep scroll inside the allowed range.
+        let scroll = min(self.relative.scroll, total_layers - visible_layers);
+
+        self.relative = Relative::new(visible_layers, panel_start, (width, height), scroll);
+    }
+
+    fn draw_panel(
+        &self,
+        painter: &mut Painter,
+        layers: &[Layer],
+        active_layer: usize,
+    ) -> crossterm::Result<()> {
+        let (x, y) = self.relative.panel_start;
+        let (width, height) = self.relative.panel_size;
+
+        if width < 3 || height < 4 {
+            return Ok(());
+        }
+
+        painter.fill(x, y, width, height, ' ')?;
+        painter.draw_box(x, y, width, height)?;
+        painter.write(x + 2, y, symbols::LAYERS_TITLE)?;
+
+        let name_width = usize::from(width.diff_or_zero(&7));
+
+        for row in 0..self.relative.visible_layers {
+            let index = self.layer_at_row(row, layers.len());
+            let layer = &layers[index];
+
+            if index == active_layer {
+                painter.set_attribute(Attribute::Reverse)?;
+            }
+
+            let visible = if layer.visible {
+                symbols::LAYER_VISIBLE
+            } else {
+                symbols::LAYER_HIDDEN
+            };
+            let locked = if layer.locked {
+                symbols::LAYER_LOCKED
+            } else {
+                symbols::LAYER_UNLOCKED
+            };
+
+            painter.write(
+                x + 1,
+                y + 1 + row as u16,
+                format!(
+                    "{} {} {:name_width$.name_width$}",
+                    visible, locked, layer.name
+                ),
+            )?;
+
+            if index == active_layer {
+                painter.set_attribute(Attribute::Reset)?;
+            }
+        }
+
+        for (button_x, button) in BUTTONS {
+            if button_x + 3 < width {
+                painter.write(x + button_x, y + height - 2, button)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Index in layers of the layer displayed in {row}, top layer is displayed first.
+    fn layer_at_row(&self, row: usize, total_layers: usize) -> usize {
+        total_layers - 1 - (row + self.relative.scroll)
+    }
+
+    // Handles mouse event, and returns the change to layers it requests.
+    // Panel has to be drawn again after any event, because scroll might have changed.
+    pub fn mouse_event(&mut self, event: MouseEvent, total_layers: usize) -> Option<LayerAction> {
+        let (click_x, click_y) = (event.column, event.row);
+
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => self.click(click_x, click_y, total_layers),
+            MouseEventKind::ScrollDown => {
+                if self.relative.visible_layers + self.relative.scroll < total_layers {
+                    self.relative.scroll += 1;
+                }
+                None
+            }
+            MouseEventKind::ScrollUp => {
+                self.relative.scroll = self.relative.scroll.diff_or_zero(&1);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn click(&self, click_x: u16, click_y: u16, total_layers: usize) -> Option<LayerAction> {
+        let (x, y) = self.relative.panel_start;
+        let (_, height) = self.relative.panel_size;
+
+        // This should never be true, but just in case.
+        if click_x < x || click_y < y {
+            return None;
+        }
+
+        let (column, row) = (click_x - x, click_y - y);
+
+        if row + 2 == height {
+            return match BUTTONS
+                .iter()
+                .position(|(button_x, _)| column >= *button_x && column < button_x + 3)
+            {
+                Some(0) => Some(LayerAction::Add),
+                Some(1) => Some(LayerAction::Delete),
+                Some(2) => Some(LayerAction::MoveUp),
+                Some(3) => Some(LayerAction::MoveDown),
+                _ => None,
+            };
+        }
+
+        // First row is the border.
+        let row = usize::from(row).checked_sub(1)?;
+
+        if row >= self.relative.visible_layers {
+            return None;
+        }
+
+        let index = self.layer_at_row(row, total_layers);
+
+        match column {
+            1 => Some(LayerAction::ToggleVisible(index)),
+            3 => Some(LayerAction::ToggleLock(index)),
+            _ => Some(LayerAction::Select(index)),
+        }
+    }
+}
+
+// Fields that depends on terminal window size.
+struct Relative {
+    visible_layers: usize,
+    panel_start: (u16, u16),
+    panel_size: (u16, u16),
+    scroll: usize,
+}
+
+impl Relative {
+    fn zero() -> Relative {
+        Relative {
+            visible_layers: 0,
+            panel_start: (0, 0),
+            panel_size: (0, 0),
+            scroll: 0,
+        }
+    }
+
+    fn new(
+        visible_layers: usize,
+        panel_start: (u16, u16),
+        panel_size: (u16, u16),
+        scroll: usize,
+    ) -> Relative {
+        Relative {
+            visible_layers,
+            panel_start,
+            panel_size,
+            scroll,
+        }
+    }
+}
@@ -9,6 +9,20 @@ use crate::{constant::symbols, painter::Painter, utils::DiffOrZero};
 
 use super::area::Area;
 
+// All tools, in the order they are shown in the panel.
+pub const TOOLS: [Tool; 10] = [
+    Tool::Select,
+    Tool::Move,
+    Tool::Rectangle,
+    Tool::Line,
+    Tool::Circle,
+    Tool::Brush,
+    Tool::Erase,
+    Tool::Bucket,
+    Tool::ColorPicker,
+    Tool::Text,
+];
+
 pub struct RightPanel {
     pub area: Area,
     tools: Vec<Tool>,
@@ -21,17 +35,7 @@ impl RightPanel {
     pub fn new(area: Area) -> RightPanel {
         RightPanel {
             area,
-            tools: vec![
-                Tool::Select,
-                Tool::Move,
-                Tool::Rectangle,
-                Tool::Circle,
-                Tool::Brush,
-                Tool::Erase,
-                Tool::Bucket,
-                Tool::ColorPicker,
-                Tool::Text,
-            ],
+            tools: TOOLS.to_vec(),
             active_tool: 0,
             brush: 'X'.cyan(),
             relative: Relative::zero(),
@@ -138,6 +142,27 @@ impl RightPanel {
         Ok(())
     }
 
+    // Selects {tool}, scrolling to it if it's not visible.
+    pub fn select_tool(&mut self, painter: &mut Painter, tool: Tool) -> crossterm::Result<()> {
+        let Some(index) = self.tools.iter().position(|other| other == &tool) else {
+            return Ok(());
+        };
+
+        self.active_tool = index;
+
+        let index = index as u16;
+        let visible_buttons = self.relative.visible_buttons;
+
+        if index < self.relative.scroll {
+            self.relative.scroll = index;
+        } else if visible_buttons > 0 && index >= self.relative.scroll + visible_buttons {
+            self.relative.scroll = index + 1 - visible_buttons;
+        }
+
+        self.draw_panel(painter)?;
+        painter.flush()
+    }
+
     fn scroll(&mut self, painter: &mut Painter, up: bool) -> crossterm::Result<()> {
         let visible_buttons = self.relative.visible_buttons;
         let total_buttons = self.tools.len() as u16;
@@ -161,10 +186,12 @@ impl RightPanel {
     }
 }
 
+#[derive(Clone, Copy, PartialEq)]
 pub enum Tool {
     Select,
     Move,
     Rectangle,
+    Line,
     Circle,
     Brush,
     Erase,
@@ -179,6 +206,7 @@ impl Tool {
             Tool::Select => symbols::SELECT,
             Tool::Move => symbols::MOVE,
             Tool::Rectangle => symbols::RECTANGLE,
+            Tool::Line => symbols::LINE,
             Tool::Circle => symbols::CIRCLE,
             Tool::Brush => symbols::BRUSH,
             Tool::Erase => symbols::ERASE,
@@ -187,6 +215,35 @@ impl Tool {
             Tool::Text => symbols::TEXT,
         }
     }
+
+    // Default key that selects this tool, the (lowercase) letter shown in its icon.
+    pub fn shortcut(&self) -> char {
+        self.icon()
+            .trim()
+            .chars()
+            .next()
+            .map_or(' ', |letter| letter.to_ascii_lowercase())
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Tool::Select => "Select",
+            Tool::Move => "Move",
+            Tool::Rectangle => "Rectangle",
+            Tool::Line => "Line",
+            Tool::Circle => "Circle",
+            Tool::Brush => "Brush",
+            Tool::Erase => "Erase",
+            Tool::Bucket => "Bucket",
+            Tool::ColorPicker => "Color Picker",
+            Tool::Text => "Text",
+        }
+    }
+
+    // Name used in the config file, e.g. "color-picker".
+    pub fn config_name(&self) -> String {
+        self.name().to_lowercase().replace(' ', "-")
+    }
 }
 
 // Fields that depends on terminal window size.
@@ -213,3 +270,91 @@ impl Relative {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyModifiers, MouseEvent, MouseEventKind};
+
+    use super::*;
+    use crate::{
+        app::area::{Corner, Point},
+        painter::memory::MemoryBackend,
+    };
+
+    fn mouse(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    // Panel with room for 3 tools.
+    fn panel() -> (RightPanel, Painter, MemoryBackend) {
+        let backend = MemoryBackend::new(5, 10);
+        let mut painter = Painter::new(Box::new(backend.clone()));
+        let mut panel = RightPanel::new(Area::new(
+            Point::new(0, 0, Corner::TopLeft),
+            Point::new(4, 1, Corner::BottomLeft),
+        ));
+
+        panel.draw(&mut painter, (5, 10)).unwrap();
+        painter.flush().unwrap();
+
+        (panel, painter, backend)
+    }
+
+    #[test]
+    fn click_selects_tool() {
+        let (mut panel, mut painter, backend) = panel();
+
+        assert_eq!(backend.line(1), "│ S │");
+        assert_eq!(backend.line(4), "│ M │");
+        assert!(backend
+            .cell(2, 1)
+            .unwrap()
+            .style()
+            .attributes
+            .has(Attribute::Reverse));
+
+        let click = mouse(MouseEventKind::Down(MouseButton::Left), 2, 4);
+        panel.mouse_event(click, &mut painter).unwrap();
+
+        assert!(panel.get_tool() == &Tool::Move);
+        assert!(!backend
+            .cell(2, 1)
+            .unwrap()
+            .style()
+            .attributes
+            .has(Attribute::Reverse));
+        assert!(backend
+            .cell(2, 4)
+            .unwrap()
+            .style()
+            .attributes
+            .has(Attribute::Reverse));
+    }
+
+    #[test]
+    fn scroll_and_select_hidden_tool() {
+        let (mut panel, mut painter, backend) = panel();
+
+        panel
+            .mouse_event(mouse(MouseEventKind::ScrollDown, 2, 4), &mut painter)
+            .unwrap();
+
+        assert_eq!(backend.line(1), "│ M │");
+        assert_eq!(backend.line(7), "│ L │");
+
+        panel.select_tool(&mut painter, Tool::Brush).unwrap();
+
+        assert_eq!(backend.line(7), "│ B │");
+        assert!(backend
+            .cell(2, 7)
+            .unwrap()
+            .style()
+            .attributes
+            .has(Attribute::Reverse));
+    }
+}
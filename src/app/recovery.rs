@@ -0,0 +1,118 @@
+// Copies of the drawing written when it could otherwise be lost, e.g. when paint-tui crashes,
+// is killed or the SSH session dies. They are in the layers format, so nothing is lost.
+
+use std::{
+    fs, io,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use paint_tui::{
+    format::{self, Format},
+    Document,
+};
+
+use crate::utils::xdg_dir;
+
+// Recovery file name, inside the recovery directory.
+const RECOVERY_FILE: &str = "recovery.ptui";
+// Written first and renamed to RECOVERY_FILE, so a crash while writing doesn't leave half of it.
+const TEMPORARY_FILE: &str = "recovery.ptui.tmp";
+// Empty file touched on every clean exit, recovery copies older than it were already dealt with.
+const CLEAN_EXIT_FILE: &str = "clean-exit";
+
+pub struct Recovery {
+    dir: PathBuf,
+}
+
+impl Recovery {
+    pub fn new(dir: PathBuf) -> Recovery {
+        Recovery { dir }
+    }
+
+    // Recovery in the paint-tui state directory, None if there is no home directory.
+    pub fn in_state_dir() -> Option<Recovery> {
+        xdg_dir("XDG_STATE_HOME", ".local/state").map(Recovery::new)
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(RECOVERY_FILE)
+    }
+
+    // Writes {document} as the recovery copy, creating the directory if needed.
+    pub fn save(&self, document: &Document) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        let temporary = self.dir.join(TEMPORARY_FILE);
+
+        fs::write(&temporary, Format::Layers.write(document))?;
+        fs::rename(temporary, self.path())
+    }
+
+    pub fn load(&self) -> io::Result<Document> {
+        format::load(self.path())
+    }
+
+    // When the recovery copy was written, if it's newer than the last clean exit.
+    pub fn pending(&self) -> Option<SystemTime> {
+        let saved = modified(self.path())?;
+
+        match modified(self.dir.join(CLEAN_EXIT_FILE)) {
+            Some(clean_exit) if clean_exit >= saved => None,
+            _ => Some(saved),
+        }
+    }
+
+    // Removes the recovery copy, it's fine if there is none.
+    pub fn discard(&self) -> io::Result<()> {
+        match fs::remove_file(self.path()) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        }
+    }
+
+    // Renames the recovery copy so the next one doesn't overwrite it. Returns the new path.
+    pub fn keep(&self) -> io::Result<PathBuf> {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        let path = self.dir.join(format!("recovery-{}.ptui", seconds));
+
+        fs::rename(self.path(), &path)?;
+
+        Ok(path)
+    }
+
+    // Records that paint-tui exited normally, so the current recovery copy isn't offered again.
+    pub fn mark_clean_exit(&self) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        // Truncating updates the modification time, with the same clock as recovery copies.
+        fs::File::create(self.dir.join(CLEAN_EXIT_FILE)).map(drop)
+    }
+}
+
+fn modified(path: PathBuf) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+// How long ago {time} was, e.g. "5 minutes ago".
+pub fn time_ago(time: SystemTime) -> String {
+    let seconds = time.elapsed().map_or(0, |elapsed| elapsed.as_secs());
+
+    let (count, unit) = match seconds {
+        0..=59 => return String::from("just now"),
+        60..=3599 => (seconds / 60, "minute"),
+        3600..=86399 => (seconds / 3600, "hour"),
+        _ => (seconds / 86400, "day"),
+    };
+
+    format!(
+        "{} {}{} ago",
+        count,
+        unit,
+        if count == 1 { "" } else { "s" }
+    )
+}
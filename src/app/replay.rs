@@ -0,0 +1,668 @@
+// Test harness that runs App on an in-memory screen, replaying events instead of reading them
+// from the terminal.
+
+use std::{
+    env, fs,
+    path::PathBuf,
+    process,
+    time::{Duration, SystemTime},
+};
+
+use crossterm::{
+    event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
+    style::StyledContent,
+};
+
+use paint_tui::{format, Mode};
+
+use crate::painter::{memory::MemoryBackend, Painter};
+
+use super::{keymap::Keymap, layer::LayerAction, panel::Tool, recovery::Recovery, App};
+
+// Screen size used by tests, with the default canvas (50x20) its content starts at CONTENT.
+const SIZE: (u16, u16) = (100, 30);
+const CONTENT: (u16, u16) = (17, 4);
+
+struct Replay {
+    app: App,
+    backend: MemoryBackend,
+    exited: bool,
+}
+
+impl Replay {
+    fn new() -> Replay {
+        Replay::with_recovery(None)
+    }
+
+    // Replay that writes recovery copies to {recovery}, and offers them on start.
+    fn with_recovery(recovery: Option<Recovery>) -> Replay {
+        let backend = MemoryBackend::new(SIZE.0, SIZE.1);
+        let mut app = App::new(Painter::new(Box::new(backend.clone())));
+
+        // Tests shouldn't depend on (or change) files of whoever runs them.
+        app.keymap = Keymap::new();
+        app.config_errors.clear();
+        app.recovery = recovery;
+        app.start().unwrap();
+
+        Replay {
+            app,
+            backend,
+            exited: false,
+        }
+    }
+
+    fn event(&mut self, event: Event) -> &mut Replay {
+        assert!(!self.exited, "event after exit");
+
+        if let Event::Resize(width, height) = event {
+            self.backend.resize(width, height);
+        }
+
+        self.exited = self.app.handle_event(event).unwrap();
+        self
+    }
+
+    fn key(&mut self, code: KeyCode) -> &mut Replay {
+        self.event(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))
+    }
+
+    // Types every character in {text}.
+    fn type_text(&mut self, text: &str) -> &mut Replay {
+        for character in text.chars() {
+            self.key(KeyCode::Char(character));
+        }
+        self
+    }
+
+    fn mouse(&mut self, kind: MouseEventKind, x: u16, y: u16) -> &mut Replay {
+        self.event(Event::Mouse(MouseEvent {
+            kind,
+            column: x,
+            row: y,
+            modifiers: KeyModifiers::NONE,
+        }))
+    }
+
+    fn press(&mut self, x: u16, y: u16) -> &mut Replay {
+        self.mouse(MouseEventKind::Down(MouseButton::Left), x, y)
+    }
+
+    fn drag(&mut self, x: u16, y: u16) -> &mut Replay {
+        self.mouse(MouseEventKind::Drag(MouseButton::Left), x, y)
+    }
+
+    fn release(&mut self, x: u16, y: u16) -> &mut Replay {
+        self.mouse(MouseEventKind::Up(MouseButton::Left), x, y)
+    }
+
+    // Presses at the first point, drags through the rest, and releases at the last one.
+    fn stroke(&mut self, points: &[(u16, u16)]) -> &mut Replay {
+        let (&(x, y), rest) = points.split_first().expect("empty stroke");
+        let &(last_x, last_y) = points.last().unwrap();
+
+        self.press(x, y);
+
+        for &(x, y) in rest {
+            self.drag(x, y);
+        }
+
+        self.release(last_x, last_y)
+    }
+
+    // Screen position of content cell (x, y), at the default zoom and offset.
+    fn content(x: u16, y: u16) -> (u16, u16) {
+        (CONTENT.0 + x, CONTENT.1 + y)
+    }
+
+    fn line(&self, y: u16) -> String {
+        self.backend.line(y)
+    }
+
+    // {width} characters of row {y}, starting at {x}.
+    fn text(&self, x: u16, y: u16, width: usize) -> String {
+        self.line(y)
+            .chars()
+            .skip(usize::from(x))
+            .take(width)
+            .collect()
+    }
+
+    fn cell(&self, x: u16, y: u16) -> Option<StyledContent<char>> {
+        self.backend.cell(x, y)
+    }
+
+    // Empty directory only used by the test called {name}.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("paint-tui-{}-{}", process::id(), name));
+
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    // Canvas content as plain text.
+    fn export(&self) -> String {
+        self.app.canvas.document().export()
+    }
+}
+
+mod tests {
+    use super::*;
+
+    // Lines of export, to compare only the rows that matter.
+    fn export_lines(replay: &Replay) -> Vec<String> {
+        replay.export().lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn brush_stroke() {
+        let mut replay = Replay::new();
+        let (x, y) = Replay::content(2, 1);
+
+        replay
+            .type_text("b")
+            .stroke(&[(x, y), (x + 1, y), (x + 2, y + 1)]);
+
+        assert_eq!(
+            export_lines(&replay)[1..3],
+            [String::from("  XX"), String::from("    X")]
+        );
+        assert_eq!(replay.text(x, y, 3), "XX ");
+        assert!(replay.app.canvas.is_dirty());
+    }
+
+    #[test]
+    fn undo_and_save_keys() {
+        let mut replay = Replay::new();
+        let (x, y) = Replay::content(0, 0);
+        let path = Replay::temp_dir("undo_and_save_keys").join("drawing.txt");
+        let ctrl = |character| {
+            Event::Key(KeyEvent::new(
+                KeyCode::Char(character),
+                KeyModifiers::CONTROL,
+            ))
+        };
+
+        // A whole stroke is one change.
+        replay
+            .type_text("b")
+            .stroke(&[(x, y), (x + 1, y), (x + 2, y)]);
+        replay.type_text("l").stroke(&[(x, y + 1), (x + 3, y + 1)]);
+
+        assert_eq!(export_lines(&replay)[..2], ["XXX", "XXXX"]);
+
+        replay.event(ctrl('z'));
+
+        assert_eq!(export_lines(&replay)[..2], ["XXX", ""]);
+
+        replay.event(ctrl('z'));
+
+        assert!(replay.export().trim().is_empty());
+
+        replay.event(ctrl('z'));
+
+        assert!(replay.line(SIZE.1 - 1).contains("Nothing to undo"));
+
+        replay.app.export_path = path.display().to_string();
+        replay.type_text("b").stroke(&[(x, y)]).event(ctrl('s'));
+
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().next(), Some("X"));
+        assert!(!replay.app.canvas.is_dirty());
+    }
+
+    #[test]
+    fn locked_layer_explains_why_nothing_is_drawn() {
+        let mut replay = Replay::new();
+        let (x, y) = Replay::content(2, 1);
+        let app = &mut replay.app;
+
+        app.canvas
+            .layer_action(&mut app.painter, LayerAction::ToggleLock(0))
+            .unwrap();
+        replay.type_text("b").stroke(&[(x, y), (x + 1, y)]);
+
+        assert!(export_lines(&replay).iter().all(String::is_empty));
+        assert!(replay.line(SIZE.1 - 1).contains("hidden or locked"));
+    }
+
+    #[test]
+    fn rectangle_dragged_past_viewport_edge() {
+        let mut replay = Replay::new();
+        let (x, y) = Replay::content(45, 15);
+
+        // Last points are outside content, and outside the canvas area.
+        replay
+            .type_text("r")
+            .stroke(&[(x, y), (x + 10, y + 3), (x + 40, y + 8)]);
+
+        let lines = export_lines(&replay);
+
+        assert_eq!(lines.len(), 20);
+        assert!(lines[..15].iter().all(String::is_empty));
+        assert!(lines[15..]
+            .iter()
+            .all(|line| line == &format!("{:45}XXXXX", "")));
+        assert_eq!(replay.text(x, y + 4, 6), "XXXXX│");
+    }
+
+    #[test]
+    fn rectangle_dragged_past_zoomed_viewport_edge() {
+        let mut replay = Replay::new();
+
+        // Content is bigger than the viewport, so the rectangle stops at the top left visible cell.
+        replay.type_text("++");
+
+        let canvas = &replay.app.canvas;
+        let visible: Vec<(u16, u16)> = (0..SIZE.1)
+            .flat_map(|y| (0..SIZE.0).map(move |x| (x, y)))
+            .filter(|&(x, y)| canvas.content_position(x, y).is_some())
+            .collect();
+        let (first, last) = (visible[0], visible[visible.len() - 1]);
+        let (offset_x, offset_y) = canvas.content_position(first.0, first.1).unwrap();
+
+        assert!(offset_x > 0 && offset_y > 0);
+
+        let middle = ((first.0 + last.0) / 2, (first.1 + last.1) / 2);
+
+        replay.type_text("r").stroke(&[middle, (0, 0)]);
+
+        let lines = export_lines(&replay);
+
+        assert!(lines[..offset_y].iter().all(String::is_empty));
+        assert!(lines[offset_y].starts_with(&format!("{:1$}X", "", offset_x)));
+    }
+
+    #[test]
+    fn keyboard_drawing() {
+        let mut replay = Replay::new();
+
+        // Cursor starts in the middle of the viewport, at (25, 10).
+        replay.type_text("b").key(KeyCode::F(4));
+        replay
+            .key(KeyCode::Right)
+            .key(KeyCode::Right)
+            .type_text(" ");
+        replay
+            .key(KeyCode::Right)
+            .key(KeyCode::Right)
+            .key(KeyCode::Enter);
+        replay.key(KeyCode::Down).type_text(" ").key(KeyCode::Enter);
+
+        // h/j/k/l move the cursor instead of selecting tools, other letters still select them.
+        replay.type_text("jh ").key(KeyCode::Enter);
+
+        assert!(matches!(replay.app.right_panel.get_tool(), Tool::Brush));
+
+        replay.type_text("L").key(KeyCode::F(4));
+
+        let lines = export_lines(&replay);
+
+        assert_eq!(lines[10], format!("{:27}XXX", ""));
+        assert_eq!(lines[11], format!("{:29}X", ""));
+        assert_eq!(lines[12], format!("{:28}X", ""));
+        assert_eq!(replay.app.canvas.cursor(), None);
+        assert!(matches!(replay.app.right_panel.get_tool(), Tool::Line));
+
+        // Without keyboard drawing they are tool shortcuts again.
+        replay.type_text("k");
+
+        assert!(matches!(replay.app.right_panel.get_tool(), Tool::Bucket));
+    }
+
+    #[test]
+    fn quit_asks_about_unsaved_changes() {
+        let mut replay = Replay::new();
+        let (x, y) = Replay::content(0, 0);
+
+        replay.type_text("b").stroke(&[(x, y)]).type_text("q");
+
+        assert!(!replay.exited);
+        assert!((0..SIZE.1).any(|y| replay.line(y).contains("Quit")));
+
+        // Cancel closes the dialog, and the screen is drawn again without it.
+        replay.type_text("c");
+
+        assert!(!replay.exited);
+        assert!(!(0..SIZE.1).any(|y| replay.line(y).contains("Quit")));
+        assert_eq!(replay.cell(x, y).map(|cell| *cell.content()), Some('X'));
+
+        // Saving would lose the second layer.
+        let app = &mut replay.app;
+        app.canvas
+            .layer_action(&mut app.painter, LayerAction::Add)
+            .unwrap();
+        replay.type_text("q");
+
+        assert!((0..SIZE.1).any(|y| replay.line(y).contains("paint-tui.ans loses layers.")));
+
+        replay.key(KeyCode::Esc);
+
+        replay.type_text("qd");
+
+        assert!(replay.exited);
+    }
+
+    #[test]
+    fn mode_switch_asks_before_losing_content() {
+        let mut replay = Replay::new();
+        let (x, y) = Replay::content(0, 0);
+
+        // An empty drawing switches without asking.
+        replay.key(KeyCode::F(2));
+
+        assert_eq!(replay.app.canvas.document().mode(), Mode::HalfBlock);
+
+        replay.key(KeyCode::F(2)).key(KeyCode::F(2));
+
+        assert_eq!(replay.app.canvas.document().mode(), Mode::Text);
+
+        // Text characters can't be pixels, so switching waits for an answer.
+        replay.type_text("b").stroke(&[(x, y)]).key(KeyCode::F(2));
+
+        assert!((0..SIZE.1).any(|y| replay.line(y).contains("half block mode loses")));
+        assert_eq!(replay.app.canvas.document().mode(), Mode::Text);
+
+        replay.type_text("c");
+
+        assert_eq!(replay.app.canvas.document().mode(), Mode::Text);
+        assert_eq!(replay.cell(x, y).map(|cell| *cell.content()), Some('X'));
+
+        replay.key(KeyCode::F(2)).type_text("s");
+
+        assert_eq!(replay.app.canvas.document().mode(), Mode::HalfBlock);
+
+        // Pixels keep their color, braille dots don't.
+        replay.key(KeyCode::F(2));
+
+        assert!((0..SIZE.1).any(|y| replay.line(y).contains("braille mode loses")));
+        assert!((0..SIZE.1).any(|y| replay.line(y).contains("colors and characters.")));
+
+        replay.key(KeyCode::Esc);
+
+        assert_eq!(replay.app.canvas.document().mode(), Mode::HalfBlock);
+    }
+
+    #[test]
+    fn mouse_reaches_lower_half() {
+        let mut replay = Replay::new();
+        let (x, y) = Replay::content(0, 0);
+        let status = SIZE.1 - 1;
+
+        replay.key(KeyCode::F(2)).type_text("b").stroke(&[(x, y)]);
+
+        assert!(replay
+            .line(status)
+            .contains("Mouse: upper half, F5/Alt: lower"));
+
+        // Alt picks the other half for one event, F5 until it's pressed again.
+        replay.event(Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: x + 1,
+            row: y,
+            modifiers: KeyModifiers::ALT,
+        }));
+        replay.key(KeyCode::F(5)).stroke(&[(x + 2, y)]);
+
+        assert!(replay
+            .line(status)
+            .contains("Mouse: lower half, F5/Alt: upper"));
+
+        let document = replay.app.canvas.document();
+        let painted: Vec<bool> = [(0, 0), (0, 1), (1, 0), (1, 1), (2, 0), (2, 1)]
+            .iter()
+            .map(|&(x, y)| !document.cell(x, y).is_empty())
+            .collect();
+
+        assert_eq!(painted, [true, false, false, true, false, true]);
+
+        // Zoomed in, every terminal cell is one pixel.
+        replay.type_text("+");
+
+        assert!(!replay.line(status).contains("Mouse:"));
+    }
+
+    #[test]
+    fn mouse_reaches_every_braille_dot() {
+        let mut replay = Replay::new();
+        let (x, y) = Replay::content(0, 0);
+        let status = SIZE.1 - 1;
+
+        replay.key(KeyCode::F(2)).key(KeyCode::F(2)).type_text("b");
+        replay.stroke(&[(x, y)]).key(KeyCode::F(5));
+
+        assert!(replay
+            .line(status)
+            .contains("Mouse: dot 2,1 of 2x4, F5: next"));
+
+        replay.stroke(&[(x + 1, y)]);
+
+        for _ in 0..5 {
+            replay.key(KeyCode::F(5));
+        }
+
+        assert!(replay.line(status).contains("Mouse: dot 1,4 of 2x4"));
+
+        replay.stroke(&[(x + 2, y)]);
+
+        let document = replay.app.canvas.document();
+
+        for (dot_x, dot_y) in [(0, 0), (3, 0), (4, 3)] {
+            assert!(!document.cell(dot_x, dot_y).is_empty());
+        }
+
+        assert!(document.export().starts_with("⠁⠈⡀\n"));
+
+        // Zoomed in, terminal cells have fewer dots, until each has one.
+        replay.type_text("+");
+
+        assert!(replay.line(status).contains("of 1x2"));
+
+        replay.type_text("+");
+
+        assert!(!replay.line(status).contains("Mouse:"));
+    }
+
+    #[test]
+    fn dialog_too_big_for_window_only_cancels() {
+        let mut replay = Replay::new();
+        let (x, y) = Replay::content(0, 0);
+
+        replay.type_text("b").stroke(&[(x, y)]);
+        replay.event(Event::Resize(30, 10)).type_text("q");
+
+        assert!((0..10).any(|y| replay.line(y).contains("Esc cancels")));
+
+        // Discard can't be seen, so it can't be chosen.
+        replay.type_text("d").key(KeyCode::Enter);
+
+        assert!(!replay.exited);
+        assert!(replay.app.dialog.is_some());
+
+        replay.key(KeyCode::Esc);
+
+        assert!(replay.app.dialog.is_none());
+    }
+
+    #[test]
+    fn resize_draws_everything_again() {
+        let mut replay = Replay::new();
+
+        replay.event(Event::Resize(60, 20));
+
+        assert!(replay.line(0).starts_with("┌───┐┌"));
+        assert!(replay.line(0).ends_with("┐"));
+        assert!(replay.line(19).contains("50x20"));
+        assert_eq!(replay.cell(60, 0), None);
+    }
+
+    #[test]
+    fn command_line() {
+        let mut replay = Replay::new();
+        let status = SIZE.1 - 1;
+
+        replay.type_text(":resize 4 2");
+
+        assert!(replay.line(status).starts_with(":resize 4 2"));
+
+        replay.key(KeyCode::Enter);
+
+        assert_eq!(replay.app.canvas.size(), (4, 2));
+        assert!(replay.line(status).contains("4x2"));
+
+        replay.type_text(":bogus").key(KeyCode::Enter);
+
+        assert!(replay.line(status).contains("Unknown command: bogus"));
+        assert_eq!(
+            replay
+                .cell(SIZE.0 - 2, status)
+                .and_then(|cell| cell.style().background_color),
+            Some(crossterm::style::Color::Red)
+        );
+    }
+
+    #[test]
+    fn import_image_with_options() {
+        let dir = Replay::temp_dir("import");
+        let path = dir.join("image.ppm");
+        let mut replay = Replay::new();
+        let status = SIZE.1 - 1;
+
+        fs::write(&path, "P3 2 2 1  1 0 0  0 1 0  0 0 1  1 1 1\n").unwrap();
+
+        let command = format!(":import {}", path.display());
+        replay.type_text(&command).key(KeyCode::Enter);
+
+        assert!((0..SIZE.1).any(|y| replay.line(y).contains("Import 2x2 image with options")));
+        assert!((0..SIZE.1).any(|y| replay.line(y).contains("dither=none|floyd|ordered")));
+        assert!((0..SIZE.1).any(|y| replay.line(y).contains("style=blocks width=80")));
+
+        for _ in "style=blocks width=80".chars() {
+            replay.key(KeyCode::Backspace);
+        }
+        replay.type_text("width=4").key(KeyCode::Enter);
+
+        assert_eq!(replay.app.canvas.size(), (4, 4));
+        assert_eq!(replay.app.canvas.document().mode(), Mode::HalfBlock);
+        assert!(replay.app.canvas.is_dirty());
+
+        // Imported drawing isn't saved, and options are remembered.
+        replay.type_text(&command).key(KeyCode::Enter);
+
+        assert!(replay.line(status).contains("use :import! to discard them"));
+
+        replay
+            .key(KeyCode::Esc)
+            .type_text(&format!(":import! {}", path.display()))
+            .key(KeyCode::Enter);
+
+        assert!((0..SIZE.1).any(|y| replay.line(y).contains("style=blocks width=4")));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn recovery_copy_of_unsaved_changes() {
+        let dir = Replay::temp_dir("recovery");
+        let mut replay = Replay::with_recovery(Some(Recovery::new(dir.join("state"))));
+        let path = dir.join("state/recovery.ptui");
+        let (x, y) = Replay::content(1, 1);
+
+        assert_eq!(replay.app.save_recovery(), None);
+        assert!(!path.exists());
+
+        let app = &mut replay.app;
+        app.canvas
+            .layer_action(&mut app.painter, LayerAction::Add)
+            .unwrap();
+        replay.type_text("b").stroke(&[(x, y), (x + 2, y)]);
+
+        let message = replay.app.save_recovery().unwrap();
+
+        assert!(message.contains(&path.display().to_string()));
+        let recovered = format::load(&path).unwrap();
+
+        assert_eq!(recovered.size(), replay.app.canvas.size());
+        assert_eq!(recovered.layers().len(), 2);
+        assert_eq!(recovered.export(), replay.export());
+        assert!(!dir.join("state/recovery.ptui.tmp").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn recovery_copy_when_drawing_fails() {
+        let dir = Replay::temp_dir("draw-error");
+        let mut replay = Replay::with_recovery(Some(Recovery::new(dir.clone())));
+        let (x, y) = Replay::content(1, 1);
+
+        replay.type_text("b").stroke(&[(x, y), (x + 2, y)]);
+        replay.backend.close();
+
+        let event = Event::Key(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE));
+
+        assert!(matches!(
+            replay.app.handle_or_end(event.clone(), false),
+            Some(Err(_))
+        ));
+        assert_eq!(
+            format::load(dir.join("recovery.ptui")).unwrap().export(),
+            replay.export()
+        );
+
+        // After a signal it's how paint-tui is expected to end.
+        assert!(matches!(
+            replay.app.handle_or_end(event, true),
+            Some(Ok(()))
+        ));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn restore_recovery_copy_on_start() {
+        let dir = Replay::temp_dir("restore");
+        let (x, y) = Replay::content(2, 3);
+
+        let mut crashed = Replay::with_recovery(Some(Recovery::new(dir.clone())));
+        crashed.type_text("b").stroke(&[(x, y), (x + 3, y)]);
+        crashed.app.save_recovery().unwrap();
+
+        // Restore.
+        let mut replay = Replay::with_recovery(Some(Recovery::new(dir.clone())));
+
+        assert!(replay.app.dialog.is_some());
+        replay.key(KeyCode::Enter);
+
+        assert!(replay.app.dialog.is_none());
+        assert_eq!(replay.export(), crashed.export());
+        assert!(replay.app.canvas.is_dirty());
+
+        // Clean exit, the same copy isn't offered again.
+        Recovery::new(dir.clone()).mark_clean_exit().unwrap();
+
+        let replay = Replay::with_recovery(Some(Recovery::new(dir.clone())));
+
+        assert!(replay.app.dialog.is_none());
+        assert_ne!(replay.export(), crashed.export());
+
+        // Keep, with Esc it's moved away so autosave doesn't overwrite it.
+        crashed.app.save_recovery().unwrap();
+
+        // File times may only change every few milliseconds, the copy has to look newer.
+        fs::File::options()
+            .write(true)
+            .open(dir.join("recovery.ptui"))
+            .and_then(|file| file.set_modified(SystemTime::now() + Duration::from_secs(1)))
+            .unwrap();
+
+        let mut replay = Replay::with_recovery(Some(Recovery::new(dir.clone())));
+        replay.key(KeyCode::Esc);
+
+        assert!(!dir.join("recovery.ptui").exists());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
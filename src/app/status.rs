@@ -0,0 +1,104 @@
+use std::time::{Duration, Instant};
+
+use crossterm::style::StyledContent;
+
+use crate::{constant::symbols, painter::Painter};
+
+use super::{area::Area, canvas::Canvas, panel::Tool};
+
+// How long a message stays in the status bar.
+const MESSAGE_DURATION: Duration = Duration::from_secs(3);
+
+// Single line at the bottom of the screen, shows information about canvas and current tool.
+pub struct StatusBar {
+    pub area: Area,
+    // Content position under the mouse pointer, None if pointer is outside content.
+    position: Option<(usize, usize)>,
+    // Transient message and the time it was set.
+    message: Option<(String, Instant)>,
+}
+
+impl StatusBar {
+    pub fn new(area: Area) -> StatusBar {
+        StatusBar {
+            area,
+            position: None,
+            message: None,
+        }
+    }
+
+    pub fn set_position(&mut self, position: Option<(usize, usize)>) {
+        self.position = position;
+    }
+
+    // Shows {message} until MESSAGE_DURATION passes.
+    pub fn set_message<S: Into<String>>(&mut self, message: S) {
+        self.message = Some((message.into(), Instant::now()));
+    }
+
+    pub fn draw(
+        &mut self,
+        painter: &mut Painter,
+        t_size: (u16, u16),
+        canvas: &Canvas,
+        tool: &Tool,
+        brush: &StyledContent<char>,
+    ) -> crossterm::Result<()> {
+        let (x, y) = self.area.start.absolute_position(t_size);
+        let (width, height) = self.area.size(t_size);
+
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+
+        if let Some((_, time)) = &self.message {
+            if time.elapsed() > MESSAGE_DURATION {
+                self.message = None;
+            }
+        }
+
+        painter.fill(x, y, width, 1, ' ')?;
+
+        let position = match self.position {
+            Some((content_x, content_y)) => format!("{}, {}", content_x, content_y),
+            None => String::from("-, -"),
+        };
+
+        let (content_width, content_height) = canvas.size();
+
+        painter.write(x + 1, y, position)?;
+        painter.write_in_place(format!(" {} {} ", symbols::VERTICAL, tool.name()))?;
+        painter.write_in_place(brush)?;
+        painter.write_in_place(format!(
+            " {} {}x{}",
+            symbols::VERTICAL,
+            content_width,
+            content_height
+        ))?;
+
+        if let Some(target) = canvas.mouse_target() {
+            painter.write_in_place(format!(" {} {}", symbols::VERTICAL, target))?;
+        }
+
+        // Other tools also use canvas selection internally, but it's not a selection for the user.
+        let selection = match tool {
+            Tool::Select | Tool::Rectangle => canvas.selection_size(),
+            _ => None,
+        };
+
+        if let Some((selection_width, selection_height)) = selection {
+            painter.write_in_place(format!(
+                " {} Selection {}x{}",
+                symbols::VERTICAL,
+                selection_width,
+                selection_height
+            ))?;
+        }
+
+        if let Some((message, _)) = &self.message {
+            painter.write_in_place(format!(" {} {}", symbols::VERTICAL, message))?;
+        }
+
+        Ok(())
+    }
+}
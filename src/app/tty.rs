@@ -0,0 +1,63 @@
+// Terminal input, only to wait for it. Events are still read with crossterm, but crossterm 0.25
+// keeps trying to read a closed terminal forever, so it can't be left waiting on one.
+// Only on Unix, elsewhere App::run waits with crossterm.
+
+use std::{
+    fs::File,
+    io::{self, IsTerminal},
+    os::fd::{AsRawFd, RawFd},
+    time::Duration,
+};
+
+pub struct Tty {
+    // Opened if stdin isn't the terminal, like crossterm does.
+    file: Option<File>,
+}
+
+impl Tty {
+    pub fn open() -> io::Result<Tty> {
+        let file = match io::stdin().is_terminal() {
+            true => None,
+            false => Some(File::open("/dev/tty")?),
+        };
+
+        Ok(Tty { file })
+    }
+
+    fn fd(&self) -> RawFd {
+        match &self.file {
+            Some(file) => file.as_raw_fd(),
+            None => io::stdin().as_raw_fd(),
+        }
+    }
+
+    // Waits until there is input, a signal comes (e.g. window resized) or {timeout} passes.
+    // Fails if the terminal was closed, e.g. the SSH session died.
+    pub fn wait(&self, timeout: Duration) -> io::Result<()> {
+        let mut poll_fd = libc::pollfd {
+            fd: self.fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout = timeout.as_millis().try_into().unwrap_or(libc::c_int::MAX);
+
+        // SAFETY: poll_fd is a valid pollfd for the whole call, and the count is 1.
+        if unsafe { libc::poll(&mut poll_fd, 1, timeout) } < 0 {
+            let error = io::Error::last_os_error();
+
+            return match error.kind() {
+                io::ErrorKind::Interrupted => Ok(()),
+                _ => Err(error),
+            };
+        }
+
+        if poll_fd.revents & (libc::POLLHUP | libc::POLLERR | libc::POLLNVAL) != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "terminal was closed",
+            ));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use crossterm::style::{ContentStyle, StyledContent};
+
+// A content cell in a layer.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Cell {
+    // Nothing is painted, layers below are visible.
+    Empty,
+    // Painted character. A painted space is still opaque, and hides layers below it.
+    Painted(StyledContent<char>),
+}
+
+impl Cell {
+    pub fn is_empty(&self) -> bool {
+        matches!(self, Cell::Empty)
+    }
+
+    // Painted character, None if empty.
+    pub fn painted(&self) -> Option<StyledContent<char>> {
+        match self {
+            Cell::Empty => None,
+            Cell::Painted(content) => Some(*content),
+        }
+    }
+
+    // What is displayed for this cell. Empty cells are displayed as a default style space.
+    pub fn display(&self) -> StyledContent<char> {
+        self.painted()
+            .unwrap_or_else(|| StyledContent::new(ContentStyle::default(), ' '))
+    }
+
+    // Character of this cell without style, used for plain text.
+    pub fn character(&self) -> char {
+        *self.display().content()
+    }
+}
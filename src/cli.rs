@@ -0,0 +1,137 @@
+// Subcommands that work on files without opening the editor, e.g. in build scripts.
+// Files are loaded and saved like in the editor, the format comes from the file extension.
+
+use std::io::{self, Write};
+
+use crossterm::style::Color;
+use paint_tui::{
+    format::{self, Format},
+    image::Image,
+    import::{self, ImportOptions},
+    Document,
+};
+
+const USAGE: &str = "\
+Usage: paint-tui                          open the editor
+       paint-tui convert INPUT OUTPUT     convert between .txt, .ans (ANSI colors)
+                                          and .ptui (layers and mode)
+       paint-tui render FILE              print FILE with colors
+       paint-tui info FILE                print size and colors of FILE
+       paint-tui import IMAGE OUTPUT [OPTION=VALUE]...
+                                          draw a PNG or PPM image with half blocks or text
+
+Import options, defaults in brackets:
+  style=blocks|ascii        width=N [80]    aspect=N [2] (terminal cell height / width)
+  colors=true|256|none      dither=none|floyd|ordered (only for colors=256)
+  contrast=N [1]            brightness=N [0] (from -1 to 1)
+  ramp=CHARACTERS           for style=ascii, from dark to bright [\" .:-=+*#%@\"]";
+
+// Runs the subcommand in {args} (without the program name).
+// Returns a message for stderr if it failed.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args.as_slice() {
+        ["convert", input, output] => {
+            let document = load(input)?;
+
+            format::save(&document, output)
+                .map_err(|error| format!("Can't write {}: {}", output, error))
+        }
+        ["import", image, output, options @ ..] => {
+            let options = ImportOptions::from_args(options.iter().copied())?;
+            let image =
+                Image::load(image).map_err(|error| format!("Can't open {}: {}", image, error))?;
+
+            format::save(&import::convert(&image, &options), output)
+                .map_err(|error| format!("Can't write {}: {}", output, error))
+        }
+        ["render", path] => print(&Format::Ansi.write(&load(path)?)),
+        ["info", path] => print(&info(&load(path)?)),
+        ["help" | "--help" | "-h"] => print(&format!("{}\n", USAGE)),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn load(path: &str) -> Result<Document, String> {
+    format::load(path).map_err(|error| format!("Can't open {}: {}", path, error))
+}
+
+// Writes {text} to stdout. A closed pipe (e.g. `| head`) isn't an error.
+fn print(text: &str) -> Result<(), String> {
+    match io::stdout().lock().write_all(text.as_bytes()) {
+        Err(error) if error.kind() != io::ErrorKind::BrokenPipe => {
+            Err(format!("Can't write to stdout: {}", error))
+        }
+        _ => Ok(()),
+    }
+}
+
+// Size of {document}, and the colors it uses in the order they first appear.
+fn info(document: &Document) -> String {
+    let (width, height) = document.size();
+    let mut foreground = Vec::new();
+    let mut background = Vec::new();
+    let mut painted = 0;
+
+    for cell in document.display().iter().flatten() {
+        let Some(cell) = cell.painted() else {
+            continue;
+        };
+
+        painted += 1;
+
+        for (colors, color) in [
+            (&mut foreground, cell.style().foreground_color),
+            (&mut background, cell.style().background_color),
+        ] {
+            match color {
+                Some(color) if !colors.contains(&color) => colors.push(color),
+                _ => {}
+            }
+        }
+    }
+
+    format!(
+        "Size: {}x{}\nPainted cells: {}\nForeground colors: {}\nBackground colors: {}\n",
+        width,
+        height,
+        painted,
+        color_list(&foreground),
+        color_list(&background),
+    )
+}
+
+fn color_list(colors: &[Color]) -> String {
+    if colors.is_empty() {
+        return String::from("none");
+    }
+
+    colors
+        .iter()
+        .map(|color| match color {
+            Color::Rgb { r, g, b } => format!("#{:02x}{:02x}{:02x}", r, g, b),
+            Color::AnsiValue(value) => format!("ansi({})", value),
+            color => format!("{:?}", color).to_lowercase(),
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn info_lists_colors_once() {
+        let document = Format::Ansi.read("\x1b[31mab\x1b[38;2;255;128;0;44mc\x1b[0m d\n");
+
+        assert_eq!(
+            info(&document),
+            "Size: 5x1\n\
+             Painted cells: 4\n\
+             Foreground colors: darkred, #ff8000\n\
+             Background colors: darkblue\n"
+        );
+    }
+}
@@ -11,14 +11,50 @@ pub mod symbols {
     pub const DASHED_HORIZONTAL: &str = "╌";
     pub const DASHED_VERTICAL: &str = "╎";
 
+    // Overlay
+    pub const GRID_DOT: char = '·';
+
+    // Layers
+    pub const LAYERS_TITLE: &str = " Layers ";
+    pub const LAYER_VISIBLE: &str = "●";
+    pub const LAYER_HIDDEN: &str = "○";
+    pub const LAYER_LOCKED: &str = "■";
+    pub const LAYER_UNLOCKED: &str = "□";
+    pub const ADD_LAYER: &str = "[+]";
+    pub const DELETE_LAYER: &str = "[-]";
+    pub const LAYER_UP: &str = "[▲]";
+    pub const LAYER_DOWN: &str = "[▼]";
+
+    // Dialogs
+    pub const QUIT_TITLE: &str = " Quit ";
+    pub const EXPORT_TITLE: &str = " Export ";
+    pub const IMPORT_TITLE: &str = " Import ";
+    pub const RECOVERY_TITLE: &str = " Recovery ";
+    pub const MODE_TITLE: &str = " Mode ";
+    pub const EXPORT_BUTTON: &str = "[ Export ]";
+    pub const IMPORT_BUTTON: &str = "[ Import ]";
+    pub const SAVE_BUTTON: &str = "[ Save ]";
+    pub const DISCARD_BUTTON: &str = "[ Discard ]";
+    pub const CANCEL_BUTTON: &str = "[ Cancel ]";
+    pub const RESTORE_BUTTON: &str = "[ Restore ]";
+    pub const KEEP_BUTTON: &str = "[ Keep ]";
+    pub const SWITCH_BUTTON: &str = "[ Switch ]";
+
+    // Scroll indicators
+    pub const SCROLL_UP: &str = "▲";
+    pub const SCROLL_DOWN: &str = "▼";
+    pub const SCROLL_LEFT: &str = "◀";
+    pub const SCROLL_RIGHT: &str = "▶";
+
     // Icons (Temp)
     pub const SELECT: &str = " S ";
     pub const MOVE: &str = " M ";
     pub const RECTANGLE: &str = " R ";
+    pub const LINE: &str = " L ";
     pub const CIRCLE: &str = " C ";
     pub const BRUSH: &str = " B ";
     pub const ERASE: &str = " E ";
     pub const BUCKET: &str = " K ";
     pub const COLOR_PICKET: &str = " P ";
     pub const TEXT: &str = " T ";
-}
\ No newline at end of file
+}
@@ -0,0 +1,804 @@
+use std::cmp::min;
+
+use crossterm::style::{Color, ContentStyle, StyledContent, Stylize};
+
+use crate::{cell::Cell, import::MAX_SIZE, layer::Layer, selection::Selection, shape, symbols};
+
+// How content cells are stored and displayed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mode {
+    // Each content cell is a character, displayed in one terminal cell.
+    Text,
+    // Each content cell is a pixel, two vertically stacked pixels are displayed in one terminal cell.
+    HalfBlock,
+    // Each content cell is a monochrome dot, 2x4 dots are displayed as one braille character.
+    Braille,
+}
+
+impl Mode {
+    // Mode after this one, when switching through all of them.
+    pub fn next(&self) -> Mode {
+        match self {
+            Mode::Text => Mode::HalfBlock,
+            Mode::HalfBlock => Mode::Braille,
+            Mode::Braille => Mode::Text,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Mode::Text => "text",
+            Mode::HalfBlock => "half block",
+            Mode::Braille => "braille",
+        }
+    }
+
+    // Number of content columns and rows displayed in one character.
+    pub fn sub_cells(&self) -> (u16, u16) {
+        match self {
+            Mode::Text => (1, 1),
+            Mode::HalfBlock => (1, 2),
+            Mode::Braille => (2, 4),
+        }
+    }
+}
+
+// A drawing made of layers of content cells. Drawing operations change the active layer,
+// and ignore positions outside content.
+#[derive(Clone)]
+pub struct Document {
+    // Ordered from bottom to top, there is always at least one layer, all of the same size.
+    layers: Vec<Layer>,
+    // Index in layers of the layer operations draw on.
+    active_layer: usize,
+    // Used for naming new layers.
+    created_layers: usize,
+    mode: Mode,
+    // Always inside content.
+    selection: Option<Selection>,
+}
+
+impl Document {
+    // Empty Text mode document with one layer, at least 1x1.
+    pub fn new(width: usize, height: usize) -> Document {
+        Document::with_layer(Layer::new("Layer 1", width.max(1), height.max(1)))
+    }
+
+    fn with_layer(layer: Layer) -> Document {
+        Document {
+            layers: vec![layer],
+            active_layer: 0,
+            created_layers: 1,
+            mode: Mode::Text,
+            selection: None,
+        }
+    }
+
+    // Text mode document with one layer of plain {text}, as wide as its longest line.
+    // Like ANSI text, it's cut at MAX_SIZE columns and rows.
+    pub fn from_text(text: &str) -> Document {
+        let rows = text
+            .lines()
+            .take(MAX_SIZE)
+            .map(|line| {
+                line.chars()
+                    .take(MAX_SIZE)
+                    .map(|character| match character {
+                        // Plain text can't tell painted and empty spaces apart.
+                        ' ' => Cell::Empty,
+                        character => {
+                            Cell::Painted(StyledContent::new(ContentStyle::default(), character))
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Document::from_cells(rows)
+    }
+
+    // Text mode document with one layer of {rows}, as wide as the longest one.
+    // Shorter rows are filled with empty cells.
+    pub fn from_cells(mut rows: Vec<Vec<Cell>>) -> Document {
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0).max(1);
+
+        if rows.is_empty() {
+            rows.push(Vec::new());
+        }
+
+        for row in &mut rows {
+            row.resize(width, Cell::Empty);
+        }
+
+        let mut layer = Layer::new("Layer 1", 0, 0);
+        layer.content = rows;
+
+        Document::with_layer(layer)
+    }
+
+    // HalfBlock mode document with one layer of {rows} of pixels, None pixels are empty.
+    pub fn from_pixels(rows: Vec<Vec<Option<Color>>>) -> Document {
+        let rows = rows
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|color| match color {
+                        Some(color) => Cell::Painted(pixel(Some(color))),
+                        None => Cell::Empty,
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Document {
+            mode: Mode::HalfBlock,
+            ..Document::from_cells(rows)
+        }
+    }
+
+    // Document of {layers} in {mode}, None unless there is at least one layer and they all have
+    // the same size (at least 1x1), or if {active_layer} isn't one of them.
+    pub fn from_layers(layers: Vec<Layer>, mode: Mode, active_layer: usize) -> Option<Document> {
+        let (width, height) = layers.first().map(|layer| {
+            (
+                layer.content.first().map_or(0, Vec::len),
+                layer.content.len(),
+            )
+        })?;
+        let same_size = layers.iter().all(|layer| {
+            layer.content.len() == height && layer.content.iter().all(|row| row.len() == width)
+        });
+
+        (same_size && width > 0 && height > 0 && active_layer < layers.len()).then_some(Document {
+            created_layers: layers.len(),
+            layers,
+            active_layer,
+            mode,
+            selection: None,
+        })
+    }
+
+    // Content size, in content cells.
+    pub fn size(&self) -> (usize, usize) {
+        let content = &self.layers[0].content;
+
+        (content[0].len(), content.len())
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    pub fn active_layer(&self) -> usize {
+        self.active_layer
+    }
+
+    // Makes the layer at {index} the one operations draw on. Ignored if there is no such layer.
+    pub fn set_active_layer(&mut self, index: usize) {
+        if index < self.layers.len() {
+            self.active_layer = index;
+        }
+    }
+
+    // Adds an empty layer above the active one, and makes it active.
+    pub fn add_layer(&mut self) {
+        let (width, height) = self.size();
+
+        self.created_layers += 1;
+        self.active_layer += 1;
+        self.layers.insert(
+            self.active_layer,
+            Layer::new(format!("Layer {}", self.created_layers), width, height),
+        );
+    }
+
+    // Removes the active layer, unless it's the only one. The layer below becomes active.
+    pub fn delete_layer(&mut self) {
+        if self.layers.len() > 1 {
+            self.layers.remove(self.active_layer);
+            self.active_layer = self.active_layer.saturating_sub(1);
+        }
+    }
+
+    // Swaps the active layer with the one above it.
+    pub fn move_layer_up(&mut self) {
+        if self.active_layer + 1 < self.layers.len() {
+            self.layers.swap(self.active_layer, self.active_layer + 1);
+            self.active_layer += 1;
+        }
+    }
+
+    // Swaps the active layer with the one below it.
+    pub fn move_layer_down(&mut self) {
+        if self.active_layer > 0 {
+            self.layers.swap(self.active_layer, self.active_layer - 1);
+            self.active_layer -= 1;
+        }
+    }
+
+    pub fn toggle_visible(&mut self, index: usize) {
+        if let Some(layer) = self.layers.get_mut(index) {
+            layer.visible = !layer.visible;
+        }
+    }
+
+    pub fn toggle_lock(&mut self, index: usize) {
+        if let Some(layer) = self.layers.get_mut(index) {
+            layer.locked = !layer.locked;
+        }
+    }
+
+    // Whether the active layer can be drawn on. Operations don't check it, so programs
+    // can still change hidden and locked layers.
+    pub fn editable(&self) -> bool {
+        let layer = &self.layers[self.active_layer];
+
+        layer.visible && !layer.locked
+    }
+
+    // Returns the top painted cell of all visible layers at (x, y),
+    // Cell::Empty if there is none or position is outside content.
+    pub fn cell(&self, x: usize, y: usize) -> Cell {
+        self.cell_at(None, x, y)
+    }
+
+    // Returns the cell at (x, y) of the layer at {index}, Cell::Empty if position is outside content.
+    pub fn layer_cell(&self, index: usize, x: usize, y: usize) -> Cell {
+        self.cell_at(Some(index), x, y)
+    }
+
+    // cell or layer_cell, depending on {layer}.
+    fn cell_at(&self, layer: Option<usize>, x: usize, y: usize) -> Cell {
+        let cell = |layer: &Layer| {
+            layer
+                .content
+                .get(y)
+                .and_then(|row| row.get(x))
+                .copied()
+                .unwrap_or(Cell::Empty)
+        };
+
+        match layer {
+            Some(index) => cell(&self.layers[index]),
+            None => self
+                .layers
+                .iter()
+                .rev()
+                .filter(|layer| layer.visible)
+                .map(cell)
+                .find(|cell| !cell.is_empty())
+                .unwrap_or(Cell::Empty),
+        }
+    }
+
+    // Converts {brush} to what is stored in content for the current mode.
+    pub fn brush(&self, brush: &StyledContent<char>) -> Cell {
+        match self.mode {
+            Mode::Text => Cell::Painted(*brush),
+            Mode::HalfBlock => Cell::Painted(pixel(brush.style().foreground_color)),
+            Mode::Braille => Cell::Painted(dot()),
+        }
+    }
+
+    // Sets the cell at (x, y) of the active layer.
+    pub fn set(&mut self, x: usize, y: usize, cell: Cell) {
+        if let Some(current) = self.layers[self.active_layer]
+            .content
+            .get_mut(y)
+            .and_then(|row| row.get_mut(x))
+        {
+            *current = cell;
+        }
+    }
+
+    // Sets every position in {points} to {cell}.
+    fn paint(&mut self, points: Vec<(usize, usize)>, cell: Cell) {
+        for (x, y) in points {
+            self.set(x, y, cell);
+        }
+    }
+
+    pub fn line(&mut self, from: (usize, usize), to: (usize, usize), cell: Cell) {
+        self.paint(shape::line(from, to), cell);
+    }
+
+    // Paints the rectangle with {selection} bounds, only its border if not {filled}.
+    pub fn rectangle(&mut self, selection: Selection, cell: Cell, filled: bool) {
+        self.paint(shape::rectangle(selection, filled), cell);
+    }
+
+    // Paints the ellipse that fits in {selection} bounds, only its outline if not {filled}.
+    pub fn ellipse(&mut self, selection: Selection, cell: Cell, filled: bool) {
+        self.paint(shape::ellipse(selection, filled), cell);
+    }
+
+    // Sets the cell at (x, y) and every cell connected to it with the same content to {cell}.
+    pub fn flood_fill(&mut self, x: usize, y: usize, cell: Cell) {
+        let (width, height) = self.size();
+        let content = &mut self.layers[self.active_layer].content;
+
+        if x >= width || y >= height || content[y][x] == cell {
+            return;
+        }
+
+        let selected = content[y][x];
+        let mut pending = vec![(x, y)];
+
+        while let Some((x, y)) = pending.pop() {
+            if content[y][x] != selected {
+                continue;
+            }
+
+            content[y][x] = cell;
+
+            if x > 0 {
+                pending.push((x - 1, y));
+            }
+            if y > 0 {
+                pending.push((x, y - 1));
+            }
+            if x + 1 < width {
+                pending.push((x + 1, y));
+            }
+            if y + 1 < height {
+                pending.push((x, y + 1));
+            }
+        }
+    }
+
+    // Sets every cell of the active layer to {cell}.
+    pub fn fill(&mut self, cell: Cell) {
+        for row in &mut self.layers[self.active_layer].content {
+            row.fill(cell);
+        }
+    }
+
+    // Changes content size of every layer, at least 1x1. New cells are empty.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let (width, height) = (width.max(1), height.max(1));
+
+        for layer in &mut self.layers {
+            layer.content.resize(height, vec![Cell::Empty; width]);
+
+            for row in &mut layer.content {
+                row.resize(width, Cell::Empty);
+            }
+        }
+
+        self.selection = None;
+    }
+
+    pub fn selection(&self) -> Option<Selection> {
+        self.selection
+    }
+
+    // Selects {selection}, with corners moved inside content.
+    pub fn select(&mut self, selection: Selection) {
+        let (width, height) = self.size();
+        let clamp = |(x, y): (usize, usize)| (min(x, width - 1), min(y, height - 1));
+
+        self.selection = Some(Selection::new(clamp(selection.start), clamp(selection.end)));
+    }
+
+    pub fn deselect(&mut self) {
+        self.selection = None;
+    }
+
+    // Switches to {mode}, converting content so the drawing looks as close as it can.
+    // Content size changes by the ratio between sub cells of the two modes.
+    pub fn set_mode(&mut self, mode: Mode) {
+        for layer in &mut self.layers {
+            layer.content = convert(&layer.content, self.mode, mode);
+        }
+
+        self.mode = mode;
+        self.selection = None;
+    }
+
+    // Switches to the next Mode, see set_mode.
+    pub fn toggle_mode(&mut self) {
+        self.set_mode(self.mode.next());
+    }
+
+    // Whether switching to {mode} loses something that switching back doesn't restore,
+    // e.g. colors in Braille mode. Text of half and full blocks converts to pixels without loss.
+    pub fn mode_change_loses(&self, mode: Mode) -> bool {
+        self.layers.iter().any(|layer| {
+            let back = convert(&convert(&layer.content, self.mode, mode), mode, self.mode);
+
+            // Sizes round up, so there may be more (empty) cells after the way back.
+            layer.content.iter().zip(&back).any(|(row, back_row)| {
+                row.iter()
+                    .zip(back_row)
+                    .any(|(&cell, &back_cell)| !same_blocks(cell, back_cell))
+            })
+        })
+    }
+
+    // Returns content as displayed characters, one per terminal cell.
+    pub fn display(&self) -> Vec<Vec<Cell>> {
+        self.display_layer(None)
+    }
+
+    // Same as display, but only for the layer at {layer} index (all visible layers if None).
+    fn display_layer(&self, layer: Option<usize>) -> Vec<Vec<Cell>> {
+        let (sub_columns, sub_rows) = self.mode.sub_cells();
+        let (content_width, content_height) = self.size();
+        let width = content_width.div_ceil(usize::from(sub_columns));
+        let height = content_height.div_ceil(usize::from(sub_rows));
+
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| self.layer_display_cell(layer, (0, 0), (1, 1), x, y))
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Returns what should be displayed at terminal cell (ix, iy), when content position {origin}
+    // is displayed at (0, 0) and each content cell has {cell_size} size in sub cells
+    // (see Mode::sub_cells). Returns Cell::Empty if nothing is displayed there.
+    pub fn display_cell(
+        &self,
+        origin: (usize, usize),
+        cell_size: (u16, u16),
+        ix: usize,
+        iy: usize,
+    ) -> Cell {
+        self.layer_display_cell(None, origin, cell_size, ix, iy)
+    }
+
+    // Same as display_cell, {layer} is the same as in display_layer.
+    fn layer_display_cell(
+        &self,
+        layer: Option<usize>,
+        origin: (usize, usize),
+        cell_size: (u16, u16),
+        ix: usize,
+        iy: usize,
+    ) -> Cell {
+        let (cell_width, cell_height) = (usize::from(cell_size.0), usize::from(cell_size.1));
+        let (origin_x, origin_y) = origin;
+
+        match self.mode {
+            Mode::Text => self.cell_at(
+                layer,
+                origin_x + ix / cell_width,
+                origin_y + iy / cell_height,
+            ),
+            Mode::HalfBlock => self.half_block(
+                layer,
+                origin_x + ix / cell_width,
+                origin_y + iy * 2 / cell_height,
+                origin_y + (iy * 2 + 1) / cell_height,
+            ),
+            Mode::Braille => {
+                let mut bits = 0;
+
+                for (dot_x, dot_y, bit) in symbols::BRAILLE_DOTS {
+                    let content_x = origin_x + (ix * 2 + dot_x) / cell_width;
+                    let content_y = origin_y + (iy * 4 + dot_y) / cell_height;
+
+                    if !self.cell_at(layer, content_x, content_y).is_empty() {
+                        bits |= bit;
+                    }
+                }
+
+                match char::from_u32(symbols::BRAILLE_BLANK as u32 + bits) {
+                    Some(braille) if bits != 0 => {
+                        Cell::Painted(StyledContent::new(ContentStyle::default(), braille))
+                    }
+                    _ => Cell::Empty,
+                }
+            }
+        }
+    }
+
+    // Combines two pixels into one character, using foreground for top and background for bottom.
+    // {layer} is the same as in cell_at.
+    fn half_block(&self, layer: Option<usize>, x: usize, top_y: usize, bottom_y: usize) -> Cell {
+        match (
+            self.pixel_color(layer, x, top_y),
+            self.pixel_color(layer, x, bottom_y),
+        ) {
+            (None, None) => Cell::Empty,
+            (Some(top), None) => Cell::Painted(symbols::UPPER_HALF_BLOCK.with(top)),
+            (None, Some(bottom)) => Cell::Painted(symbols::LOWER_HALF_BLOCK.with(bottom)),
+            (Some(top), Some(bottom)) => {
+                Cell::Painted(symbols::UPPER_HALF_BLOCK.with(top).on(bottom))
+            }
+        }
+    }
+
+    // Color of a pixel in HalfBlock mode, None if it's empty or outside content.
+    fn pixel_color(&self, layer: Option<usize>, x: usize, y: usize) -> Option<Color> {
+        self.cell_at(layer, x, y)
+            .painted()
+            .map(|cell| cell.style().foreground_color.unwrap_or(Color::Reset))
+    }
+
+    // Returns content as plain text, without colors. In Braille mode this is the braille text.
+    // Empty cells at the end of a line are removed, but painted spaces are kept.
+    pub fn export(&self) -> String {
+        self.display_lines()
+            .iter()
+            .map(|line| line.iter().map(Cell::character).collect::<String>() + "\n")
+            .collect()
+    }
+
+    // Same as display, without empty cells at the end of each row.
+    fn display_lines(&self) -> Vec<Vec<Cell>> {
+        let mut rows = self.display();
+
+        for row in &mut rows {
+            let length = row
+                .iter()
+                .rposition(|cell| !cell.is_empty())
+                .map_or(0, |index| index + 1);
+
+            row.truncate(length);
+        }
+
+        rows
+    }
+}
+
+// {content} of a layer in {from} mode, converted to {to} mode. See sub_cell for text.
+fn convert(content: &[Vec<Cell>], from: Mode, to: Mode) -> Vec<Vec<Cell>> {
+    let (width, height) = (content[0].len(), content.len());
+    let painted = |x: usize, y: usize| {
+        content
+            .get(y)
+            .and_then(|row| row.get(x))
+            .is_some_and(|cell| !cell.is_empty())
+    };
+
+    match (from, to) {
+        (from, Mode::Text) => {
+            // Each terminal cell becomes the character it was displayed with.
+            let mut layer = Layer::new("", 0, 0);
+            layer.content = content.to_vec();
+
+            Document {
+                mode: from,
+                ..Document::with_layer(layer)
+            }
+            .display_layer(Some(0))
+        }
+        (Mode::Text, to) => {
+            let (columns, rows) = to.sub_cells();
+            let (columns, rows) = (usize::from(columns), usize::from(rows));
+
+            (0..height * rows)
+                .map(|y| {
+                    (0..width * columns)
+                        .map(|x| {
+                            sub_cell(content[y / rows][x / columns], to, x % columns, y % rows)
+                        })
+                        .collect()
+                })
+                .collect()
+        }
+        // Each pixel becomes 2x2 dots, which cover the same part of a terminal cell.
+        (Mode::HalfBlock, Mode::Braille) => (0..height * 2)
+            .map(|y| {
+                (0..width * 2)
+                    .map(|x| match painted(x / 2, y / 2) {
+                        true => Cell::Painted(dot()),
+                        false => Cell::Empty,
+                    })
+                    .collect()
+            })
+            .collect(),
+        // Each 2x2 dots become a pixel, if any of them is set.
+        (Mode::Braille, Mode::HalfBlock) => (0..height.div_ceil(2))
+            .map(|y| {
+                (0..width.div_ceil(2))
+                    .map(|x| {
+                        let dots = [(0, 0), (1, 0), (0, 1), (1, 1)];
+
+                        match dots.iter().any(|(dx, dy)| painted(x * 2 + dx, y * 2 + dy)) {
+                            true => Cell::Painted(pixel(None)),
+                            false => Cell::Empty,
+                        }
+                    })
+                    .collect()
+            })
+            .collect(),
+        (Mode::HalfBlock, Mode::HalfBlock) | (Mode::Braille, Mode::Braille) => content.to_vec(),
+    }
+}
+
+// Whether {cell} and {other} are equal, or are both half blocks with the same two pixels
+// (a ▄ with a background comes back as a ▀ with the colors swapped).
+fn same_blocks(cell: Cell, other: Cell) -> bool {
+    let half_block = |cell: Cell| {
+        cell.painted().is_some_and(|cell| {
+            [symbols::UPPER_HALF_BLOCK, symbols::LOWER_HALF_BLOCK].contains(cell.content())
+        })
+    };
+
+    cell == other
+        || half_block(cell)
+            && half_block(other)
+            && (0..2).all(|y| {
+                sub_cell(cell, Mode::HalfBlock, 0, y) == sub_cell(other, Mode::HalfBlock, 0, y)
+            })
+}
+
+// Sub cell (sub_x, sub_y) of text {cell} in {mode}, see Mode::sub_cells. Half and full blocks
+// become pixels of their colors, and braille characters their dots. Other characters fill
+// the whole cell, with their foreground color in HalfBlock mode.
+fn sub_cell(cell: Cell, mode: Mode, sub_x: usize, sub_y: usize) -> Cell {
+    let Cell::Painted(cell) = cell else {
+        return Cell::Empty;
+    };
+    let (foreground, background) = (
+        Some(cell.style().foreground_color),
+        cell.style().background_color.map(Some),
+    );
+    let character = *cell.content();
+
+    match mode {
+        Mode::Text => Cell::Painted(cell),
+        Mode::HalfBlock => {
+            let color = match (character, sub_y) {
+                (symbols::UPPER_HALF_BLOCK, 1) | (symbols::LOWER_HALF_BLOCK, 0) | (' ', _) => {
+                    background
+                }
+                _ => foreground,
+            };
+
+            color.map_or(Cell::Empty, |color| Cell::Painted(pixel(color)))
+        }
+        Mode::Braille => {
+            let bits = match character {
+                ' ' => 0,
+                symbols::BRAILLE_BLANK..='\u{28ff}' => {
+                    character as u32 - symbols::BRAILLE_BLANK as u32
+                }
+                _ => 0xff,
+            };
+            let set = symbols::BRAILLE_DOTS
+                .iter()
+                .any(|&(x, y, bit)| (x, y) == (sub_x, sub_y) && bits & bit != 0);
+
+            match set {
+                true => Cell::Painted(dot()),
+                false => Cell::Empty,
+            }
+        }
+    }
+}
+
+// Braille dots are monochrome, so all set dots are the same cell.
+fn dot() -> StyledContent<char> {
+    StyledContent::new(ContentStyle::default(), symbols::BRAILLE_FULL)
+}
+
+// Pixel with {color}. Pixels only keep the color, so cells with the same color are equal.
+fn pixel(color: Option<Color>) -> StyledContent<char> {
+    symbols::FULL_BLOCK.with(color.unwrap_or(Color::Reset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn painted(character: char) -> Cell {
+        Cell::Painted(StyledContent::new(ContentStyle::default(), character))
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let document = Document::from_text("ab\n\n c\n");
+
+        assert_eq!(document.size(), (2, 3));
+        assert_eq!(document.cell(1, 2), painted('c'));
+        assert!(document.cell(0, 2).is_empty());
+        assert_eq!(document.export(), "ab\n\n c\n");
+    }
+
+    #[test]
+    fn operations_change_active_layer() {
+        let mut document = Document::new(5, 3);
+
+        document.rectangle(Selection::new((0, 0), (4, 2)), painted('#'), false);
+        document.add_layer();
+        document.line((0, 1), (4, 1), painted('-'));
+
+        assert_eq!(document.layers().len(), 2);
+        assert_eq!(document.layers()[1].name, "Layer 2");
+        assert_eq!(document.export(), "#####\n-----\n#####\n");
+
+        document.toggle_visible(1);
+
+        assert_eq!(document.export(), "#####\n#   #\n#####\n");
+    }
+
+    #[test]
+    fn flood_fill_stops_at_other_cells() {
+        let mut document = Document::from_text("#####\n#   #\n## ##\n");
+
+        document.flood_fill(1, 1, painted('o'));
+
+        assert_eq!(document.export(), "#####\n#ooo#\n##o##\n");
+    }
+
+    #[test]
+    fn selection_stays_inside_content() {
+        let mut document = Document::new(4, 4);
+
+        document.select(Selection::new((2, 1), (10, 10)));
+
+        assert_eq!(document.selection(), Some(Selection::new((2, 1), (3, 3))));
+
+        document.resize(2, 2);
+
+        assert_eq!(document.selection(), None);
+    }
+
+    #[test]
+    fn modes_convert_content() {
+        let mut document = Document::from_text("x\n");
+
+        assert!(document.mode_change_loses(Mode::HalfBlock));
+
+        document.toggle_mode();
+
+        assert_eq!(document.mode(), Mode::HalfBlock);
+        assert_eq!(document.size(), (1, 2));
+        assert_eq!(document.export(), "▀\n");
+
+        document.toggle_mode();
+
+        assert_eq!(document.size(), (2, 4));
+        assert_eq!(document.export(), "⣿\n");
+
+        document.toggle_mode();
+
+        assert_eq!(document.mode(), Mode::Text);
+        assert_eq!(document.export(), "⣿\n");
+        assert!(!document.mode_change_loses(Mode::Braille));
+    }
+
+    #[test]
+    fn half_blocks_convert_without_loss() {
+        let blocks = [
+            symbols::UPPER_HALF_BLOCK.red().on_blue(),
+            symbols::LOWER_HALF_BLOCK.green(),
+            symbols::UPPER_HALF_BLOCK.with(Color::Reset),
+            symbols::LOWER_HALF_BLOCK.yellow().on_cyan(),
+        ];
+        let mut document = Document::from_cells(vec![blocks.map(Cell::Painted).to_vec()]);
+        let text = document.layers()[0].content.clone();
+
+        assert!(!document.mode_change_loses(Mode::HalfBlock));
+
+        document.set_mode(Mode::HalfBlock);
+
+        assert_eq!(document.size(), (4, 2));
+        assert_eq!(document.cell(0, 1), Cell::Painted(pixel(Some(Color::Blue))));
+        assert!(document.cell(1, 0).is_empty());
+        assert!(document.cell(2, 1).is_empty());
+        assert!(document.mode_change_loses(Mode::Braille));
+        assert!(!document.mode_change_loses(Mode::Text));
+
+        document.set_mode(Mode::Text);
+
+        assert_eq!(document.layers()[0].content[0][..3], text[0][..3]);
+        assert_eq!(
+            document.cell(3, 0),
+            Cell::Painted(symbols::UPPER_HALF_BLOCK.cyan().on_yellow())
+        );
+        assert!(
+            Document::from_cells(vec![vec![Cell::Painted(' '.on_yellow())]])
+                .mode_change_loses(Mode::HalfBlock)
+        );
+    }
+}
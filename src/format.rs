@@ -0,0 +1,116 @@
+// Loading and saving drawings, the file format is picked from the file extension.
+
+mod ansi;
+mod layers;
+
+use std::{fs, io, path::Path};
+
+use crossterm::style::ContentStyle;
+
+use crate::document::{Document, Mode};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    // Plain text without colors, the default for unknown extensions.
+    Text,
+    // Text with ANSI escape codes for colors and attributes (.ans).
+    Ansi,
+    // Layers with their state, and the mode, so nothing is lost (.ptui).
+    Layers,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Format {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("ans") => Format::Ansi,
+            Some(extension) if extension.eq_ignore_ascii_case("ptui") => Format::Layers,
+            _ => Format::Text,
+        }
+    }
+
+    // Document with the drawing in {text}. Text in any format can be read, nothing is an error.
+    pub fn read(&self, text: &str) -> Document {
+        match self {
+            Format::Text => Document::from_text(text),
+            Format::Ansi => ansi::read(text),
+            // Anything else is read as the ANSI text it mostly is.
+            Format::Layers => layers::read(text).unwrap_or_else(|| ansi::read(text)),
+        }
+    }
+
+    // What writing {document} in this format loses, e.g. ["colors", "layers"]. Empty if nothing.
+    pub fn lost(&self, document: &Document) -> Vec<&'static str> {
+        let layers = document.layers();
+        let mut lost = Vec::new();
+
+        if *self == Format::Layers {
+            return lost;
+        }
+
+        if *self == Format::Text
+            && document
+                .display()
+                .iter()
+                .flatten()
+                .any(|cell| *cell.display().style() != ContentStyle::default())
+        {
+            lost.push("colors");
+        }
+
+        if layers.len() > 1 || layers.iter().any(|layer| !layer.visible || layer.locked) {
+            lost.push("layers");
+        }
+
+        if document.mode() != Mode::Text {
+            lost.push("the mode");
+        }
+
+        lost
+    }
+
+    // {document} as displayed, in this format.
+    pub fn write(&self, document: &Document) -> String {
+        match self {
+            Format::Text => document.export(),
+            Format::Ansi => ansi::write(document),
+            Format::Layers => layers::write(document),
+        }
+    }
+}
+
+// Opens the drawing at {path}. Invalid UTF-8 is replaced, since ANSI art often isn't UTF-8.
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Document> {
+    let path = path.as_ref();
+    let bytes = fs::read(path)?;
+
+    Ok(Format::from_path(path).read(&String::from_utf8_lossy(&bytes)))
+}
+
+// Writes {document} to {path}, replacing it if it exists.
+pub fn save<P: AsRef<Path>>(document: &Document, path: P) -> io::Result<()> {
+    let path = path.as_ref();
+
+    fs::write(path, Format::from_path(path).write(document))
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::style::Stylize;
+
+    use super::*;
+    use crate::cell::Cell;
+
+    #[test]
+    fn lost_lists_what_only_other_formats_keep() {
+        let mut document = Document::from_text("ab\n");
+
+        assert!(Format::Text.lost(&document).is_empty());
+
+        document.set(0, 0, Cell::Painted('a'.red()));
+        document.add_layer();
+
+        assert_eq!(Format::Text.lost(&document), ["colors", "layers"]);
+        assert_eq!(Format::Ansi.lost(&document), ["layers"]);
+        assert!(Format::Layers.lost(&document).is_empty());
+    }
+}
@@ -0,0 +1,305 @@
+// Text with SGR escape codes ("\x1b[...m"), the usual format of ANSI art (.ans files).
+
+use crossterm::style::{Attribute, Color, Colored, ContentStyle, StyledContent};
+
+use crate::{cell::Cell, document::Document, import::MAX_SIZE};
+
+// Reads colored text into a Text mode document. Cursor forward codes ("\x1b[nC") leave cells
+// empty, other escape codes are ignored. Rows are cut at MAX_SIZE columns, and only the first
+// MAX_SIZE rows are read.
+pub fn read(text: &str) -> Document {
+    // SAUCE metadata may follow the end of file character, it isn't part of the drawing.
+    let text = text.split('\x1a').next().unwrap_or_default();
+    let mut rows = read_rows(text, false, MAX_SIZE);
+
+    rows.truncate(MAX_SIZE);
+
+    Document::from_cells(rows)
+}
+
+// Rows of cells in {text}, as read reads them but cut at {max_width} columns.
+// Spaces without a background are empty, unless {painted_spaces}.
+pub(super) fn read_rows(text: &str, painted_spaces: bool, max_width: usize) -> Vec<Vec<Cell>> {
+    let mut rows = vec![Vec::new()];
+    let mut x: usize = 0;
+    let mut style = ContentStyle::default();
+    let mut characters = text.chars();
+
+    while let Some(character) = characters.next() {
+        match character {
+            '\x1b' => {
+                let Some('[') = characters.next() else {
+                    continue;
+                };
+
+                // Parameters go until the final byte, which says what the sequence does.
+                let mut parameters = String::new();
+                let final_byte = characters.by_ref().find(|&character| {
+                    parameters.push(character);
+                    ('@'..='~').contains(&character)
+                });
+                parameters.pop();
+
+                match final_byte {
+                    Some('m') => apply_sgr(&mut style, &parameters),
+                    Some('C') => {
+                        let columns = parameters.parse().unwrap_or(1).max(1);
+                        x = x.saturating_add(columns).min(max_width);
+                    }
+                    _ => {}
+                }
+            }
+            '\n' => {
+                rows.push(Vec::new());
+                x = 0;
+            }
+            '\r' => x = 0,
+            character if character.is_control() || x >= max_width => {}
+            character => {
+                let row = rows.last_mut().unwrap();
+
+                if row.len() <= x {
+                    row.resize(x + 1, Cell::Empty);
+                }
+
+                // Spaces are only visible with a background.
+                row[x] = if character == ' '
+                    && !painted_spaces
+                    && style.background_color.is_none()
+                    && !style.attributes.has(Attribute::Reverse)
+                {
+                    Cell::Empty
+                } else {
+                    Cell::Painted(StyledContent::new(style, character))
+                };
+                x += 1;
+            }
+        }
+    }
+
+    // A newline at the end doesn't start another row.
+    if rows.len() > 1 && rows.last().is_some_and(Vec::is_empty) {
+        rows.pop();
+    }
+
+    rows
+}
+
+// Changes {style} with the codes in SGR {parameters} (e.g. "1;38;5;9").
+fn apply_sgr(style: &mut ContentStyle, parameters: &str) {
+    let mut codes = parameters.split(';');
+
+    while let Some(code) = codes.next() {
+        // Missing codes are 0, e.g. "\x1b[m" resets.
+        let code: u8 = match code {
+            "" => 0,
+            code => match code.parse() {
+                Ok(code) => code,
+                Err(_) => continue,
+            },
+        };
+        let attributes = &mut style.attributes;
+
+        match code {
+            0 => *style = ContentStyle::default(),
+            1 => attributes.set(Attribute::Bold),
+            2 => attributes.set(Attribute::Dim),
+            3 => attributes.set(Attribute::Italic),
+            4 => attributes.set(Attribute::Underlined),
+            5 => attributes.set(Attribute::SlowBlink),
+            7 => attributes.set(Attribute::Reverse),
+            8 => attributes.set(Attribute::Hidden),
+            9 => attributes.set(Attribute::CrossedOut),
+            22 => {
+                attributes.unset(Attribute::Bold);
+                attributes.unset(Attribute::Dim);
+            }
+            23 => attributes.unset(Attribute::Italic),
+            24 => attributes.unset(Attribute::Underlined),
+            25 => attributes.unset(Attribute::SlowBlink),
+            27 => attributes.unset(Attribute::Reverse),
+            28 => attributes.unset(Attribute::Hidden),
+            29 => attributes.unset(Attribute::CrossedOut),
+            30..=37 => style.foreground_color = Color::parse_ansi(&format!("5;{}", code - 30)),
+            90..=97 => style.foreground_color = Color::parse_ansi(&format!("5;{}", code - 82)),
+            40..=47 => style.background_color = Color::parse_ansi(&format!("5;{}", code - 40)),
+            100..=107 => style.background_color = Color::parse_ansi(&format!("5;{}", code - 92)),
+            38 => style.foreground_color = extended_color(&mut codes),
+            48 => style.background_color = extended_color(&mut codes),
+            58 => style.underline_color = extended_color(&mut codes),
+            39 => style.foreground_color = None,
+            49 => style.background_color = None,
+            59 => style.underline_color = None,
+            _ => {}
+        }
+    }
+}
+
+// Color after a 38, 48 or 58 code: "5;n" (256 colors) or "2;r;g;b" (true color).
+fn extended_color<'a>(codes: &mut impl Iterator<Item = &'a str>) -> Option<Color> {
+    let (kind, count) = match codes.next()? {
+        "5" => ("5", 1),
+        "2" => ("2", 3),
+        _ => return None,
+    };
+    let values: Vec<&str> = codes.take(count).collect();
+
+    Color::parse_ansi(&format!("{};{}", kind, values.join(";")))
+}
+
+// Writes {document} as displayed, a style is only written when it changes.
+// Every line that changes the style ends with a reset, so lines can be printed on their own.
+// Empty cells at the end of lines are kept as spaces, so reading it back gives the same size.
+pub fn write(document: &Document) -> String {
+    write_rows(&document.display(), false)
+}
+
+// Writes {rows} like write. If {skip_empty}, empty cells are written as cursor forward codes
+// instead of spaces, and empty cells at the end of lines aren't written.
+pub(super) fn write_rows(rows: &[Vec<Cell>], skip_empty: bool) -> String {
+    let mut text = String::new();
+
+    for line in rows {
+        let mut style = ContentStyle::default();
+        let mut skipped = 0;
+
+        for cell in line {
+            if skip_empty && cell.is_empty() {
+                skipped += 1;
+                continue;
+            }
+
+            if skipped > 0 {
+                text.push_str(&format!("\x1b[{}C", skipped));
+                skipped = 0;
+            }
+
+            let cell = cell.display();
+
+            if *cell.style() != style {
+                style = *cell.style();
+                text.push_str(&sgr(&style));
+            }
+
+            text.push(*cell.content());
+        }
+
+        if style != ContentStyle::default() {
+            text.push_str("\x1b[0m");
+        }
+
+        text.push('\n');
+    }
+
+    text
+}
+
+// Escape code that sets exactly {style}, starting from a reset.
+fn sgr(style: &ContentStyle) -> String {
+    let mut codes = vec![String::from("0")];
+
+    codes.extend(
+        Attribute::iterator()
+            .filter(|attribute| style.attributes.has(*attribute))
+            .map(|attribute| attribute.sgr()),
+    );
+
+    let colors = [
+        style.foreground_color.map(Colored::ForegroundColor),
+        style.background_color.map(Colored::BackgroundColor),
+        style.underline_color.map(Colored::UnderlineColor),
+    ];
+
+    codes.extend(
+        colors
+            .into_iter()
+            .flatten()
+            .map(|colored| colored.to_string()),
+    );
+
+    format!("\x1b[{}m", codes.join(";"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::style::Stylize;
+
+    use super::*;
+
+    #[test]
+    fn reads_colors_and_attributes() {
+        let document = read(
+            "\x1b[1;31mA\x1b[22;38;2;1;2;3;44m \x1b[0m\x1b[2CB\r\x1b[93mC\n\x1b[m\n\x1a SAUCE",
+        );
+
+        assert_eq!(document.size(), (5, 2));
+        assert_eq!(document.cell(0, 0), Cell::Painted('C'.with(Color::Yellow)));
+        assert_eq!(
+            document.cell(1, 0),
+            Cell::Painted(
+                ' '.with(Color::Rgb { r: 1, g: 2, b: 3 })
+                    .on(Color::DarkBlue)
+            )
+        );
+        assert!(document.cell(2, 0).is_empty());
+        assert_eq!(
+            document.cell(4, 0),
+            Cell::Painted(StyledContent::new(ContentStyle::default(), 'B'))
+        );
+        assert_eq!(document.export(), "C   B\n\n");
+    }
+
+    #[test]
+    fn cursor_forward_stops_at_max_size() {
+        let document = read("\x1b[4000000000CA\x1b[999CB\nC");
+
+        assert_eq!(document.size(), (1, 2));
+        assert_eq!(
+            read(&format!("\x1b[{}CAB", MAX_SIZE - 1)).size(),
+            (MAX_SIZE, 1)
+        );
+        assert_eq!(read(&"A\n".repeat(MAX_SIZE + 5)).size(), (1, MAX_SIZE));
+    }
+
+    #[test]
+    fn plain_text_stops_at_max_size() {
+        let long_line = "x".repeat(MAX_SIZE * 3);
+        let document = Document::from_text(&format!("{}\nab\n", long_line));
+
+        assert_eq!(document.size(), (MAX_SIZE, 2));
+        assert_eq!(
+            Document::from_text(&"a\n".repeat(MAX_SIZE + 5)).size(),
+            (1, MAX_SIZE)
+        );
+    }
+
+    #[test]
+    fn write_then_read_keeps_styles() {
+        let document = Document::from_cells(vec![
+            vec![
+                Cell::Painted('x'.red().bold()),
+                Cell::Empty,
+                Cell::Painted('y'.on(Color::AnsiValue(208)).underlined()),
+            ],
+            vec![Cell::Painted(StyledContent::new(
+                ContentStyle::default(),
+                'z',
+            ))],
+        ]);
+
+        let text = write(&document);
+
+        assert_eq!(
+            text,
+            "\x1b[0;1;38;5;9mx\x1b[0m \x1b[0;4;48;5;208my\x1b[0m\nz  \n"
+        );
+
+        let read = read(&text);
+
+        assert_eq!(read.size(), document.size());
+
+        for (x, y) in [(0, 0), (1, 0), (2, 0), (0, 1)] {
+            assert_eq!(read.cell(x, y), document.cell(x, y));
+        }
+    }
+}
@@ -0,0 +1,154 @@
+// paint-tui's own format (.ptui), which keeps layers and the mode. It's text:
+//
+//   paint-tui 1 halfblock 50 40 0       version, mode, width, height and active layer
+//   visible unlocked Layer 1            then for every layer, from the bottom,
+//   ...                                 its state and name, and height rows of ANSI text
+//
+// Empty cells are cursor forward codes, so painted spaces stay painted.
+
+use crate::{cell::Cell, document::Document, import::MAX_SIZE, layer::Layer, Mode};
+
+use super::ansi;
+
+const HEADER: &str = "paint-tui 1";
+// Biggest width and height read, braille dots of the biggest drawings in other modes.
+const MAX_DOTS: usize = MAX_SIZE * 4;
+
+// Document in {text}, None if it isn't a valid layers file.
+pub fn read(text: &str) -> Option<Document> {
+    let mut lines = text.lines();
+    let header = lines.next()?.strip_prefix(HEADER)?;
+    let fields: Vec<&str> = header.split_whitespace().collect();
+
+    let [mode, width, height, active] = fields[..] else {
+        return None;
+    };
+
+    let mode = match mode {
+        "text" => Mode::Text,
+        "halfblock" => Mode::HalfBlock,
+        "braille" => Mode::Braille,
+        _ => return None,
+    };
+    let number = |field: &str| field.parse::<usize>().ok();
+    let (width, height, active) = (number(width)?, number(height)?, number(active)?);
+
+    if width > MAX_DOTS || height > MAX_DOTS {
+        return None;
+    }
+
+    let mut layers = Vec::new();
+
+    while let Some(line) = lines.next() {
+        let mut state = line.splitn(3, ' ');
+        let visible = match state.next()? {
+            "visible" => true,
+            "hidden" => false,
+            _ => return None,
+        };
+        let locked = match state.next()? {
+            "locked" => true,
+            "unlocked" => false,
+            _ => return None,
+        };
+
+        let mut layer = Layer::new(state.next().unwrap_or_default(), 0, 0);
+        layer.visible = visible;
+        layer.locked = locked;
+
+        for _ in 0..height {
+            let mut row = ansi::read_rows(lines.next()?, true, width).swap_remove(0);
+
+            row.resize(width, Cell::Empty);
+            layer.content.push(row);
+        }
+
+        layers.push(layer);
+    }
+
+    Document::from_layers(layers, mode, active)
+}
+
+pub fn write(document: &Document) -> String {
+    let (width, height) = document.size();
+    let mode = match document.mode() {
+        Mode::Text => "text",
+        Mode::HalfBlock => "halfblock",
+        Mode::Braille => "braille",
+    };
+
+    let mut text = format!(
+        "{} {} {} {} {}\n",
+        HEADER,
+        mode,
+        width,
+        height,
+        document.active_layer()
+    );
+
+    for layer in document.layers() {
+        text.push_str(&format!(
+            "{} {} {}\n",
+            if layer.visible { "visible" } else { "hidden" },
+            if layer.locked { "locked" } else { "unlocked" },
+            layer.name
+        ));
+        text.push_str(&ansi::write_rows(&layer.content, true));
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::style::{Color, ContentStyle, StyledContent, Stylize};
+
+    use super::*;
+
+    #[test]
+    fn write_then_read_keeps_layers_and_mode() {
+        let mut document = Document::from_pixels(vec![
+            vec![Some(Color::Red), None, Some(Color::Blue)],
+            vec![None; 3],
+            vec![None, Some(Color::Green), None],
+        ]);
+
+        document.add_layer();
+        document.set(
+            1,
+            1,
+            Cell::Painted(StyledContent::new(ContentStyle::default(), ' ')),
+        );
+        document.toggle_lock(1);
+        document.toggle_visible(0);
+        document.set_active_layer(0);
+
+        let read = read(&write(&document)).unwrap();
+
+        assert_eq!(read.mode(), Mode::HalfBlock);
+        assert_eq!(read.size(), (3, 3));
+        assert_eq!(read.active_layer(), 0);
+
+        for (read, layer) in read.layers().iter().zip(document.layers()) {
+            assert_eq!(read.name, layer.name);
+            assert_eq!((read.visible, read.locked), (layer.visible, layer.locked));
+            assert_eq!(read.content, layer.content);
+        }
+
+        assert_eq!(read.layers().len(), 2);
+        assert!(read.layers()[1].content[1][1].painted().is_some());
+        assert_eq!(
+            read.layers()[0].content[0][2],
+            Cell::Painted('█'.with(Color::Blue))
+        );
+    }
+
+    #[test]
+    fn invalid_files_are_not_read() {
+        assert!(read("paint-tui 1 text 2 1 0\nvisible unlocked Layer 1\nab\n").is_some());
+        assert!(read("paint-tui 1 text 2 1 1\nvisible unlocked Layer 1\nab\n").is_none());
+        assert!(read("paint-tui 1 text 2 2 0\nvisible unlocked Layer 1\nab\n").is_none());
+        assert!(read("paint-tui 1 text 9999999999 1 0\nvisible unlocked Layer 1\n\n").is_none());
+        assert!(read("ab\n").is_none());
+    }
+}
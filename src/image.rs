@@ -0,0 +1,185 @@
+// Raster images (PNG and PPM) that can be imported as drawings, see the import module.
+
+mod ppm;
+
+use std::{fs, io, path::Path};
+
+// Red, green, blue and alpha (0 is transparent).
+pub type Rgba = [u8; 4];
+
+pub struct Image {
+    width: usize,
+    height: usize,
+    // Row by row, width * height of them.
+    pixels: Vec<Rgba>,
+}
+
+impl Image {
+    // Image of {width}x{height} {pixels}, None if their number doesn't match.
+    pub fn new(width: usize, height: usize, pixels: Vec<Rgba>) -> Option<Image> {
+        (width > 0 && height > 0 && pixels.len() == width * height).then_some(Image {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    // Reads a PNG or PPM file, the format comes from its first bytes.
+    pub fn read(bytes: &[u8]) -> io::Result<Image> {
+        let image = match bytes {
+            [0x89, b'P', b'N', b'G', ..] => read_png(bytes)?,
+            [b'P', b'3' | b'6', ..] => ppm::read(bytes)?,
+            _ => return Err(invalid_data("not a PNG or PPM image")),
+        };
+
+        image.ok_or_else(|| invalid_data("image has no pixels"))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Image> {
+        Image::read(&fs::read(path)?)
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Rgba {
+        self.pixels[y * self.width + x]
+    }
+
+    // Scales to {width}x{height}, each new pixel is the average of the pixels it covers.
+    // Colors are weighted by alpha, so transparent pixels don't darken edges.
+    pub fn resize(&self, width: usize, height: usize) -> Image {
+        let (width, height) = (width.max(1), height.max(1));
+        let mut pixels = Vec::with_capacity(width * height);
+
+        // Source pixels covered by new pixel {index}, at least one.
+        let span = |index: usize, size: usize, source: usize| {
+            let start = index * source / size;
+            start..((index + 1) * source / size).max(start + 1)
+        };
+
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = [0u64; 4];
+                let mut count = 0;
+
+                for source_y in span(y, height, self.height) {
+                    for source_x in span(x, width, self.width) {
+                        let [r, g, b, a] = self.pixel(source_x, source_y).map(u64::from);
+
+                        sum[0] += r * a;
+                        sum[1] += g * a;
+                        sum[2] += b * a;
+                        sum[3] += a;
+                        count += 1;
+                    }
+                }
+
+                pixels.push(match sum[3] {
+                    0 => [0; 4],
+                    alpha => [
+                        (sum[0] / alpha) as u8,
+                        (sum[1] / alpha) as u8,
+                        (sum[2] / alpha) as u8,
+                        (alpha / count) as u8,
+                    ],
+                });
+            }
+        }
+
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+fn read_png(bytes: &[u8]) -> io::Result<Option<Image>> {
+    let mut decoder = png::Decoder::new(bytes);
+    // Palettes and low bit depths become 8 bit gray or RGB, with alpha if there is transparency.
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    let samples = &buffer[..info.buffer_size()];
+
+    let pixels = match info.color_type {
+        png::ColorType::Grayscale => samples.iter().map(|&v| [v, v, v, 255]).collect(),
+        png::ColorType::GrayscaleAlpha => samples
+            .chunks_exact(2)
+            .map(|gray| [gray[0], gray[0], gray[0], gray[1]])
+            .collect(),
+        png::ColorType::Rgb => samples
+            .chunks_exact(3)
+            .map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+            .collect(),
+        png::ColorType::Rgba => samples
+            .chunks_exact(4)
+            .map(|rgba| [rgba[0], rgba[1], rgba[2], rgba[3]])
+            .collect(),
+        png::ColorType::Indexed => return Err(invalid_data("PNG palette wasn't expanded")),
+    };
+
+    Ok(Image::new(
+        info.width as usize,
+        info.height as usize,
+        pixels,
+    ))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resize_averages_opaque_pixels() {
+        let image = Image::new(
+            4,
+            2,
+            vec![
+                [200, 0, 0, 255],
+                [0, 0, 200, 255],
+                [9, 9, 9, 255],
+                [9, 9, 9, 255],
+                [0, 0, 0, 0],
+                [0, 0, 100, 255],
+                [9, 9, 9, 255],
+                [9, 9, 9, 255],
+            ],
+        )
+        .unwrap();
+
+        let resized = image.resize(2, 1);
+
+        assert_eq!(resized.size(), (2, 1));
+        // The transparent pixel only makes it less opaque.
+        assert_eq!(resized.pixel(0, 0), [66, 0, 100, 191]);
+        assert_eq!(resized.pixel(1, 0), [9, 9, 9, 255]);
+        assert_eq!(image.resize(8, 2).pixel(7, 1), [9, 9, 9, 255]);
+    }
+
+    #[test]
+    fn reads_png() {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, 2, 1);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(&[255, 0, 0, 255, 0, 0, 0, 0])
+            .unwrap();
+
+        let image = Image::read(&bytes).unwrap();
+
+        assert_eq!(image.size(), (2, 1));
+        assert_eq!(image.pixel(0, 0), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(1, 0)[3], 0);
+    }
+}
@@ -0,0 +1,108 @@
+// Netpbm color images, plain (P3, numbers as text) or raw (P6, binary samples).
+
+use std::io;
+
+use super::{invalid_data, Image};
+
+// Reads a P3 or P6 image, samples are scaled from the max value in the header to 0-255.
+pub fn read(bytes: &[u8]) -> io::Result<Option<Image>> {
+    let mut position = 2;
+    let mut header = [0; 3];
+
+    for value in &mut header {
+        *value = number(bytes, &mut position)?;
+    }
+
+    let [width, height, max] = header;
+
+    if !(1..=u16::MAX as usize).contains(&max) {
+        return Err(invalid_data("PPM max value must be from 1 to 65535"));
+    }
+
+    let count = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(3))
+        .ok_or_else(|| invalid_data("PPM image is too big"))?;
+
+    let samples: Vec<usize> = match bytes[1] {
+        b'3' => (0..count)
+            .map(|_| number(bytes, &mut position))
+            .collect::<io::Result<_>>()?,
+        _ => {
+            // Exactly one whitespace character separates the header from samples.
+            let data = bytes.get(position + 1..).unwrap_or_default();
+            let size = if max < 256 { 1 } else { 2 };
+
+            if data.len() / size < count {
+                return Err(invalid_data("PPM file is too short"));
+            }
+
+            data.chunks_exact(size)
+                .take(count)
+                .map(|sample| match sample {
+                    [high, low] => usize::from(*high) << 8 | usize::from(*low),
+                    sample => usize::from(sample[0]),
+                })
+                .collect()
+        }
+    };
+
+    let pixels = samples
+        .chunks_exact(3)
+        .map(|rgb| {
+            let [r, g, b] = [0, 1, 2].map(|channel| (rgb[channel].min(max) * 255 / max) as u8);
+            [r, g, b, 255]
+        })
+        .collect();
+
+    Ok(Image::new(width, height, pixels))
+}
+
+// Reads the number after whitespace and comments at {position}, and moves past it.
+fn number(bytes: &[u8], position: &mut usize) -> io::Result<usize> {
+    loop {
+        match bytes.get(*position) {
+            Some(b'#') => {
+                while !matches!(bytes.get(*position), Some(b'\n') | None) {
+                    *position += 1;
+                }
+            }
+            Some(byte) if byte.is_ascii_whitespace() => *position += 1,
+            _ => break,
+        }
+    }
+
+    let start = *position;
+
+    while bytes.get(*position).is_some_and(u8::is_ascii_digit) {
+        *position += 1;
+    }
+
+    std::str::from_utf8(&bytes[start..*position])
+        .ok()
+        .and_then(|digits| digits.parse().ok())
+        .ok_or_else(|| invalid_data("expected a number in PPM file"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_and_raw_are_the_same() {
+        let plain = read(b"P3\n# comment\n2 1 15\n15 0 0  0 15 15\n")
+            .unwrap()
+            .unwrap();
+        let raw = read(b"P6 2 1 255\n\xff\x00\x00\x00\xff\xff")
+            .unwrap()
+            .unwrap();
+
+        for image in [plain, raw] {
+            assert_eq!(image.size(), (2, 1));
+            assert_eq!(image.pixel(0, 0), [255, 0, 0, 255]);
+            assert_eq!(image.pixel(1, 0), [0, 255, 255, 255]);
+        }
+
+        assert!(read(b"P6 2 1 255\n\xff").is_err());
+    }
+}
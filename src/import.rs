@@ -0,0 +1,591 @@
+// Converting images into drawings, e.g. to trace over a logo.
+
+use std::fmt::{self, Display, Formatter};
+
+use crossterm::style::{Color, ContentStyle, StyledContent, Stylize};
+
+use crate::{cell::Cell, document::Document, image::Image};
+
+// Biggest drawing width or height, in content cells.
+pub const MAX_SIZE: usize = 1000;
+
+// Levels of each channel in the 6x6x6 color cube of 256 color terminals (colors 16 to 231).
+const CUBE_LEVELS: [f32; 6] = [0.0, 95.0, 135.0, 175.0, 215.0, 255.0];
+// Ordered dithering thresholds, from 0 to 15.
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+// How far ordered dithering moves colors, about the distance between cube levels.
+const ORDERED_SPREAD: f32 = 40.0;
+// Characters for Style::Ascii, from dark to bright.
+const DEFAULT_RAMP: &str = " .:-=+*#%@";
+
+// What cells images are drawn with.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Style {
+    // Colored pixels, two per terminal cell (a HalfBlock mode document).
+    HalfBlocks,
+    // Characters from a ramp, brighter pixels get later characters (a Text mode document).
+    Ascii,
+}
+
+// Colors cells can use.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Palette {
+    // 24 bit colors, exactly like the image.
+    TrueColor,
+    // Colors 16 to 255 of 256 color terminals, the first 16 depend on the terminal theme.
+    Ansi256,
+    // Only the default color, e.g. for plain text. Half blocks only keep the image shape.
+    Monochrome,
+}
+
+// How colors that aren't in the palette are approximated.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Dither {
+    // Nearest color.
+    None,
+    // Error of each pixel is spread to the next pixels.
+    FloydSteinberg,
+    // Pixels are moved by a repeating pattern, which looks less noisy.
+    Ordered,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ImportOptions {
+    pub style: Style,
+    // Drawing width in terminal cells.
+    pub width: usize,
+    // Terminal cell height divided by its width, so the image isn't stretched.
+    pub aspect: f64,
+    pub palette: Palette,
+    // Only used with Palette::Ansi256, true color doesn't need it.
+    pub dither: Dither,
+    // Multiplies the distance of colors from middle gray, 1 keeps them.
+    pub contrast: f64,
+    // Added to colors, from -1 (all black) to 1 (all white).
+    pub brightness: f64,
+    // Characters for Style::Ascii, from dark to bright, at least two.
+    pub ramp: Vec<char>,
+}
+
+impl Default for ImportOptions {
+    fn default() -> ImportOptions {
+        ImportOptions {
+            style: Style::HalfBlocks,
+            width: 80,
+            aspect: 2.0,
+            palette: Palette::TrueColor,
+            dither: Dither::None,
+            contrast: 1.0,
+            brightness: 0.0,
+            ramp: DEFAULT_RAMP.chars().collect(),
+        }
+    }
+}
+
+impl ImportOptions {
+    // Parses options like "width=60 colors=256 dither=ordered", missing ones are the default.
+    // Values can be in double quotes, to have spaces (e.g. ramp=" .oO").
+    // Returns a message if one is invalid.
+    pub fn parse(text: &str) -> Result<ImportOptions, String> {
+        ImportOptions::from_args(words(text)?.iter().map(String::as_str))
+    }
+
+    // Same as parse, for options that are already separate (e.g. command line arguments),
+    // so values are used as they are, without quotes.
+    pub fn from_args<'a, I: IntoIterator<Item = &'a str>>(
+        args: I,
+    ) -> Result<ImportOptions, String> {
+        let mut options = ImportOptions::default();
+
+        for option in args {
+            let Some((name, value)) = option.split_once('=') else {
+                return Err(format!("Expected name=value, found {}", option));
+            };
+
+            match (name, value) {
+                ("style", "blocks") => options.style = Style::HalfBlocks,
+                ("style", "ascii") => options.style = Style::Ascii,
+                ("style", _) => return Err(String::from("Expected style=blocks or style=ascii")),
+                ("width", width) => {
+                    options.width = match width.parse() {
+                        Ok(width @ 1..=MAX_SIZE) => width,
+                        _ => return Err(format!("Expected width from 1 to {}", MAX_SIZE)),
+                    }
+                }
+                ("aspect", aspect) => {
+                    options.aspect = match aspect.parse() {
+                        Ok(aspect) if (0.1..=10.0).contains(&aspect) => aspect,
+                        _ => return Err(String::from("Expected aspect from 0.1 to 10")),
+                    }
+                }
+                ("colors", "true") => options.palette = Palette::TrueColor,
+                ("colors", "256") => options.palette = Palette::Ansi256,
+                ("colors", "none") => options.palette = Palette::Monochrome,
+                ("colors", _) => return Err(String::from("Expected colors=true|256|none")),
+                ("dither", "none") => options.dither = Dither::None,
+                ("dither", "floyd") => options.dither = Dither::FloydSteinberg,
+                ("dither", "ordered") => options.dither = Dither::Ordered,
+                ("dither", _) => return Err(String::from("Expected dither=none|floyd|ordered")),
+                ("contrast", contrast) => {
+                    options.contrast = match contrast.parse() {
+                        Ok(contrast) if (0.0..=10.0).contains(&contrast) => contrast,
+                        _ => return Err(String::from("Expected contrast from 0 to 10")),
+                    }
+                }
+                ("brightness", brightness) => {
+                    options.brightness = match brightness.parse() {
+                        Ok(brightness) if (-1.0..=1.0).contains(&brightness) => brightness,
+                        _ => return Err(String::from("Expected brightness from -1 to 1")),
+                    }
+                }
+                ("ramp", ramp) if ramp.chars().count() >= 2 => {
+                    options.ramp = ramp.chars().collect()
+                }
+                ("ramp", _) => return Err(String::from("Expected at least two ramp characters")),
+                (name, _) => return Err(format!("Unknown option: {}", name)),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+// Splits {text} at whitespace outside double quotes, and removes the quotes.
+fn words(text: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quoted = false;
+
+    for character in text.chars() {
+        match character {
+            '"' => quoted = !quoted,
+            character if character.is_whitespace() && !quoted => {
+                words.extend(word.take());
+                continue;
+            }
+            character => word.get_or_insert_with(String::new).push(character),
+        }
+
+        // Quotes start a word too, "" is an empty one.
+        word.get_or_insert_with(String::new);
+    }
+
+    if quoted {
+        return Err(String::from("Missing closing quote"));
+    }
+
+    words.extend(word);
+
+    Ok(words)
+}
+
+// Options as parse reads them. Only style, width and options that aren't the default are written,
+// so they are short enough to edit.
+impl Display for ImportOptions {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let default = ImportOptions::default();
+        let style = match self.style {
+            Style::HalfBlocks => "blocks",
+            Style::Ascii => "ascii",
+        };
+
+        write!(f, "style={} width={}", style, self.width)?;
+
+        if self.aspect != default.aspect {
+            write!(f, " aspect={}", self.aspect)?;
+        }
+
+        if self.palette != default.palette {
+            let palette = match self.palette {
+                Palette::TrueColor => "true",
+                Palette::Ansi256 => "256",
+                Palette::Monochrome => "none",
+            };
+
+            write!(f, " colors={}", palette)?;
+        }
+
+        if self.dither != default.dither {
+            let dither = match self.dither {
+                Dither::None => "none",
+                Dither::FloydSteinberg => "floyd",
+                Dither::Ordered => "ordered",
+            };
+
+            write!(f, " dither={}", dither)?;
+        }
+
+        if self.contrast != default.contrast {
+            write!(f, " contrast={}", self.contrast)?;
+        }
+
+        if self.brightness != default.brightness {
+            write!(f, " brightness={}", self.brightness)?;
+        }
+
+        if self.ramp != default.ramp {
+            write!(f, " ramp=\"{}\"", self.ramp.iter().collect::<String>())?;
+        }
+
+        Ok(())
+    }
+}
+
+// Converts {image} into a drawing with options style.
+pub fn convert(image: &Image, options: &ImportOptions) -> Document {
+    match options.style {
+        Style::HalfBlocks => half_blocks(image, options),
+        Style::Ascii => ascii(image, options),
+    }
+}
+
+// HalfBlock mode document, two pixels per terminal cell. Mostly transparent pixels are empty.
+fn half_blocks(image: &Image, options: &ImportOptions) -> Document {
+    // Pixels are half of a terminal cell high.
+    let (width, height) = scaled_size(image, options, options.aspect / 2.0);
+    let pixels = adjusted(&image.resize(width, height), options);
+
+    Document::from_pixels(colors(pixels, options.palette, options.dither))
+}
+
+// Text mode document, each cell is a ramp character for the luminance of the pixels it covers.
+// Mostly transparent pixels are empty, and so are spaces.
+fn ascii(image: &Image, options: &ImportOptions) -> Document {
+    let (width, height) = scaled_size(image, options, options.aspect);
+    let pixels = adjusted(&image.resize(width, height), options);
+    let colors = colors(pixels.clone(), options.palette, options.dither);
+    let last = options.ramp.len() - 1;
+
+    let rows = pixels
+        .iter()
+        .zip(colors)
+        .map(|(pixels, colors)| {
+            pixels
+                .iter()
+                .zip(colors)
+                .map(|(pixel, color)| {
+                    let Some([r, g, b]) = pixel else {
+                        return Cell::Empty;
+                    };
+
+                    // Relative luminance (Rec. 709), from 0 to 1.
+                    let luminance = (0.2126 * r + 0.7152 * g + 0.0722 * b) / 255.0;
+                    let character = options.ramp[(luminance * last as f32).round() as usize];
+
+                    match (character, color) {
+                        (' ', _) => Cell::Empty,
+                        (character, Some(color)) if options.palette != Palette::Monochrome => {
+                            Cell::Painted(character.with(color))
+                        }
+                        (character, _) => {
+                            Cell::Painted(StyledContent::new(ContentStyle::default(), character))
+                        }
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    Document::from_cells(rows)
+}
+
+// Size in content cells of {image} scaled to options width, with cells {aspect} times
+// higher than wide.
+fn scaled_size(image: &Image, options: &ImportOptions, aspect: f64) -> (usize, usize) {
+    let (width, height) = image.size();
+    let scale = options.width as f64 / width as f64;
+
+    let rows = (height as f64 * scale / aspect).round() as usize;
+
+    (options.width, rows.clamp(1, MAX_SIZE))
+}
+
+// Colors of {image} pixels with options contrast and brightness, from 0 to 255.
+// None for mostly transparent pixels.
+fn adjusted(image: &Image, options: &ImportOptions) -> Vec<Vec<Option<[f32; 3]>>> {
+    let (width, height) = image.size();
+    let (contrast, brightness) = (options.contrast as f32, options.brightness as f32);
+
+    (0..height)
+        .map(|y| {
+            (0..width)
+                .map(|x| {
+                    let [r, g, b, a] = image.pixel(x, y);
+
+                    (a >= 128).then_some([r, g, b].map(|channel| {
+                        let value = (f32::from(channel) / 255.0 - 0.5) * contrast + 0.5;
+                        ((value + brightness) * 255.0).clamp(0.0, 255.0)
+                    }))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// {pixels} colors in {palette}, None for transparent pixels.
+fn colors(
+    mut pixels: Vec<Vec<Option<[f32; 3]>>>,
+    palette: Palette,
+    dither: Dither,
+) -> Vec<Vec<Option<Color>>> {
+    let mut rows = Vec::with_capacity(pixels.len());
+
+    for y in 0..pixels.len() {
+        let mut row = Vec::with_capacity(pixels[y].len());
+
+        for x in 0..pixels[y].len() {
+            // Floyd-Steinberg adds errors of previous pixels to them.
+            let Some(color) = pixels[y][x] else {
+                row.push(None);
+                continue;
+            };
+
+            let color = color.map(|channel| channel.clamp(0.0, 255.0));
+
+            row.push(Some(match palette {
+                Palette::Monochrome => Color::Reset,
+                Palette::TrueColor => {
+                    let [r, g, b] = color.map(|channel| channel.round() as u8);
+                    Color::Rgb { r, g, b }
+                }
+                Palette::Ansi256 => {
+                    let target = match dither {
+                        Dither::Ordered => {
+                            let threshold = (f32::from(BAYER[y % 4][x % 4]) + 0.5) / 16.0 - 0.5;
+                            color.map(|channel| channel + threshold * ORDERED_SPREAD)
+                        }
+                        _ => color,
+                    };
+                    let (index, chosen) = nearest_ansi(target);
+
+                    if dither == Dither::FloydSteinberg {
+                        let error = [0, 1, 2].map(|channel| color[channel] - chosen[channel]);
+
+                        for (dx, dy, weight) in
+                            [(1, 0, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)]
+                        {
+                            let next = pixels
+                                .get_mut(y + dy)
+                                .and_then(|row| row.get_mut(x.wrapping_add_signed(dx)));
+
+                            if let Some(Some(next)) = next {
+                                for channel in 0..3 {
+                                    next[channel] += error[channel] * weight / 16.0;
+                                }
+                            }
+                        }
+                    }
+
+                    Color::AnsiValue(index)
+                }
+            }));
+        }
+
+        rows.push(row);
+    }
+
+    rows
+}
+
+// Nearest color to {color} in the color cube or the gray ramp of 256 color terminals.
+// Returns its index and value.
+fn nearest_ansi(color: [f32; 3]) -> (u8, [f32; 3]) {
+    let distance = |other: [f32; 3]| {
+        (0..3)
+            .map(|channel| (color[channel] - other[channel]).powi(2))
+            .sum::<f32>()
+    };
+
+    let levels = color.map(|channel| {
+        (0..CUBE_LEVELS.len())
+            .min_by(|&a, &b| {
+                (CUBE_LEVELS[a] - channel)
+                    .abs()
+                    .total_cmp(&(CUBE_LEVELS[b] - channel).abs())
+            })
+            .unwrap()
+    });
+    let cube = levels.map(|level| CUBE_LEVELS[level]);
+    let cube_index = 16 + 36 * levels[0] + 6 * levels[1] + levels[2];
+
+    // Grays 232 to 255 go from 8 to 238, in steps of 10.
+    let average = color.iter().sum::<f32>() / 3.0;
+    let step = ((average - 8.0) / 10.0).round().clamp(0.0, 23.0);
+    let gray = [8.0 + step * 10.0; 3];
+
+    if distance(gray) < distance(cube) {
+        (232 + step as u8, gray)
+    } else {
+        (cube_index as u8, cube)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cell::Cell, document::Mode};
+
+    fn image(width: usize, height: usize, pixel: impl Fn(usize, usize) -> [u8; 4]) -> Image {
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| pixel(x, y))
+            .collect();
+
+        Image::new(width, height, pixels).unwrap()
+    }
+
+    #[test]
+    fn options_round_trip() {
+        let options = ImportOptions::parse("dither=ordered colors=256 width=60").unwrap();
+
+        assert_eq!(options.width, 60);
+        assert_eq!(options.dither, Dither::Ordered);
+        assert_eq!(ImportOptions::parse(&options.to_string()), Ok(options));
+        assert!(ImportOptions::parse("width=0").is_err());
+        assert!(ImportOptions::parse("dither").is_err());
+
+        let options = ImportOptions::parse("style=ascii ramp=\" .o\" contrast=1.5").unwrap();
+
+        assert_eq!(options.ramp, [' ', '.', 'o']);
+        assert_eq!(
+            options.to_string(),
+            "style=ascii width=80 contrast=1.5 ramp=\" .o\""
+        );
+        assert_eq!(ImportOptions::parse(&options.to_string()), Ok(options));
+        assert!(ImportOptions::parse("ramp=\" .o").is_err());
+
+        // Arguments aren't split or unquoted again.
+        let options = ImportOptions::from_args(["ramp= \"'#", "width=3"]).unwrap();
+
+        assert_eq!(options.ramp, [' ', '"', '\'', '#']);
+        assert_eq!(options.width, 3);
+    }
+
+    #[test]
+    fn half_blocks_keep_aspect_and_transparency() {
+        // Red left half, transparent right half.
+        let image = image(4, 4, |x, _| match x {
+            0 | 1 => [255, 0, 0, 255],
+            _ => [0; 4],
+        });
+        let options = ImportOptions {
+            width: 2,
+            ..ImportOptions::default()
+        };
+
+        let document = half_blocks(&image, &options);
+
+        assert_eq!(document.mode(), Mode::HalfBlock);
+        assert_eq!(document.size(), (2, 2));
+        assert!(document.cell(1, 1).is_empty());
+        assert_eq!(document.export(), "▀\n");
+        assert_eq!(
+            document
+                .cell(0, 1)
+                .painted()
+                .and_then(|cell| cell.style().foreground_color),
+            Some(Color::Rgb { r: 255, g: 0, b: 0 })
+        );
+
+        // Cells twice as high as wide, like most terminal fonts, give half as many pixel rows.
+        let options = ImportOptions {
+            aspect: 4.0,
+            ..options
+        };
+
+        assert_eq!(half_blocks(&image, &options).size(), (2, 1));
+    }
+
+    #[test]
+    fn ascii_maps_luminance_to_ramp() {
+        // Black, dark gray, light gray and white, then transparent.
+        let image = image(5, 1, |x, _| match x {
+            4 => [0; 4],
+            x => [(x * 85) as u8, (x * 85) as u8, (x * 85) as u8, 255],
+        });
+        let options = ImportOptions {
+            style: Style::Ascii,
+            width: 5,
+            aspect: 1.0,
+            palette: Palette::Monochrome,
+            ramp: vec![' ', '.', 'o', '@'],
+            ..ImportOptions::default()
+        };
+
+        let document = convert(&image, &options);
+
+        assert_eq!(document.mode(), Mode::Text);
+        assert_eq!(document.export(), " .o@\n");
+        assert!(document.cell(0, 0).is_empty());
+        assert_eq!(
+            document.cell(3, 0),
+            Cell::Painted(StyledContent::new(ContentStyle::default(), '@'))
+        );
+
+        // Contrast moves grays away from the middle, brightness moves all of them.
+        let contrast = ImportOptions {
+            contrast: 3.0,
+            ..options.clone()
+        };
+        let brightness = ImportOptions {
+            brightness: 0.4,
+            ..options.clone()
+        };
+
+        assert_eq!(convert(&image, &contrast).export(), "  @@\n");
+        assert_eq!(convert(&image, &brightness).export(), ".o@@\n");
+
+        let colored = ImportOptions {
+            palette: Palette::TrueColor,
+            ..options
+        };
+
+        assert_eq!(
+            convert(&image, &colored).cell(1, 0),
+            Cell::Painted('.'.with(Color::Rgb {
+                r: 85,
+                g: 85,
+                b: 85
+            }))
+        );
+    }
+
+    #[test]
+    fn dithering_mixes_nearest_colors() {
+        // Between blue levels 95 (color 17) and 135 (color 18).
+        let image = image(8, 8, |_, _| [0, 0, 115, 255]);
+
+        for (dither, expected) in [
+            (Dither::None, vec![17]),
+            (Dither::FloydSteinberg, vec![17, 18]),
+            (Dither::Ordered, vec![17, 18]),
+        ] {
+            let options = ImportOptions {
+                width: 8,
+                aspect: 2.0,
+                palette: Palette::Ansi256,
+                dither,
+                ..ImportOptions::default()
+            };
+            let document = half_blocks(&image, &options);
+            let mut indices: Vec<u8> = (0..8)
+                .flat_map(|y| (0..8).map(move |x| (x, y)))
+                .filter_map(|(x, y)| match document.cell(x, y) {
+                    Cell::Painted(cell) => match cell.style().foreground_color {
+                        Some(Color::AnsiValue(index)) => Some(index),
+                        _ => None,
+                    },
+                    Cell::Empty => None,
+                })
+                .collect();
+
+            indices.sort();
+            indices.dedup();
+
+            assert_eq!(indices, expected, "{:?}", dither);
+        }
+
+        assert_eq!(nearest_ansi([255.0, 0.0, 0.0]).0, 196);
+        assert_eq!(nearest_ansi([128.0, 128.0, 128.0]).0, 244);
+    }
+}
@@ -0,0 +1,22 @@
+use crate::cell::Cell;
+
+#[derive(Clone)]
+pub struct Layer {
+    pub name: String,
+    pub visible: bool,
+    // Locked layers can't be drawn on.
+    pub locked: bool,
+    pub content: Vec<Vec<Cell>>,
+}
+
+impl Layer {
+    // New layer with only empty cells.
+    pub fn new<S: Into<String>>(name: S, width: usize, height: usize) -> Layer {
+        Layer {
+            name: name.into(),
+            visible: true,
+            locked: false,
+            content: vec![vec![Cell::Empty; width]; height],
+        }
+    }
+}
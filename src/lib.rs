@@ -0,0 +1,23 @@
+// Drawings without the terminal UI, so other programs can generate, change and export them.
+//
+//   let mut document = Document::new(20, 5);
+//   let brush = document.brush(&'#'.red());
+//
+//   document.rectangle(Selection::new((0, 0), (19, 4)), brush, false);
+//   document.line((2, 2), (17, 2), brush);
+//   fs::write("drawing.txt", document.export())?;
+
+pub mod cell;
+pub mod document;
+pub mod format;
+pub mod image;
+pub mod import;
+pub mod layer;
+pub mod selection;
+pub mod shape;
+mod symbols;
+
+pub use cell::Cell;
+pub use document::{Document, Mode};
+pub use layer::Layer;
+pub use selection::Selection;
@@ -1,16 +1,57 @@
 mod app;
+mod cli;
 mod constant;
 mod painter;
 mod utils;
 
-use std::io;
+use std::{
+    env, io,
+    panic::{self, AssertUnwindSafe},
+    process,
+};
 
 use app::App;
+use painter::{restore_terminal, Painter, StdoutBackend};
 
 fn main() -> crossterm::Result<()> {
-    let stdout = io::stdout();
+    let args: Vec<String> = env::args().skip(1).collect();
 
-    let mut app = App::new(stdout);
+    // Any argument is a subcommand, which doesn't open the editor.
+    if !args.is_empty() {
+        if let Err(error) = cli::run(&args) {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
 
-    app.run()
+        return Ok(());
+    }
+
+    let painter = Painter::new(Box::new(StdoutBackend::new(io::stdout())));
+
+    let mut app = App::new(painter);
+
+    // The panic message would be lost with the alternate screen, so terminal is restored first.
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+        default_hook(info);
+    }));
+
+    match panic::catch_unwind(AssertUnwindSafe(|| app.run())) {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(error)) => {
+            // Error may come before the app restored the terminal, doing it twice is harmless.
+            let _ = restore_terminal();
+
+            Err(error)
+        }
+        Err(panic) => {
+            // Content may be half changed by whatever panicked, but that's better than nothing.
+            if let Some(message) = app.save_recovery() {
+                eprintln!("{}", message);
+            }
+
+            panic::resume_unwind(panic)
+        }
+    }
 }
@@ -1,95 +1,162 @@
-use std::{
-    fmt::Display,
-    io::{self, Write},
-    time::Duration,
-};
-
-use crossterm::{
-    cursor,
-    event::{poll, read, DisableMouseCapture, EnableMouseCapture},
-    execute, queue,
-    style::{Attribute, Print, SetAttribute},
-    terminal::{
-        Clear, ClearType, DisableLineWrap, EnableLineWrap, EnterAlternateScreen,
-        LeaveAlternateScreen,
-    },
-    ExecutableCommand, QueueableCommand,
-};
+mod buffer;
+#[cfg(test)]
+pub mod memory;
+mod stdout;
+
+use std::fmt::Display;
+
+use crossterm::style::{Attribute, Color, ContentStyle, StyledContent};
+
+use self::buffer::Buffer;
 
 use crate::constant::symbols;
 
+pub use stdout::{restore_terminal, StdoutBackend};
+
+// Where Painter draws to. It works like a terminal with line wrap disabled: text is printed
+// at the cursor with the current attributes and colors, which last until they are changed.
+pub trait Backend {
+    // Prepares the screen for drawing, e.g. switching to the alternate screen.
+    fn start(&mut self) -> crossterm::Result<()>;
+    // Gives the screen back as it was before start.
+    fn stop(&mut self) -> crossterm::Result<()>;
+    fn size(&self) -> crossterm::Result<(u16, u16)>;
+    fn move_to(&mut self, x: u16, y: u16) -> crossterm::Result<()>;
+    // Prints at the cursor and moves it after the text.
+    fn print(&mut self, text: &str) -> crossterm::Result<()>;
+    // Attribute::Reset also resets colors.
+    fn set_attribute(&mut self, attribute: Attribute) -> crossterm::Result<()>;
+    fn set_foreground(&mut self, color: Color) -> crossterm::Result<()>;
+    fn set_background(&mut self, color: Color) -> crossterm::Result<()>;
+    fn set_underline_color(&mut self, color: Color) -> crossterm::Result<()>;
+    fn clear(&mut self) -> crossterm::Result<()>;
+    fn flush(&mut self) -> crossterm::Result<()>;
+}
+
+// Something Painter can print: text, with or without style.
+pub trait Content {
+    fn styled(self) -> StyledContent<String>;
+}
+
+impl Content for char {
+    fn styled(self) -> StyledContent<String> {
+        StyledContent::new(ContentStyle::new(), self.to_string())
+    }
+}
+
+impl Content for &str {
+    fn styled(self) -> StyledContent<String> {
+        StyledContent::new(ContentStyle::new(), self.to_string())
+    }
+}
+
+impl Content for String {
+    fn styled(self) -> StyledContent<String> {
+        StyledContent::new(ContentStyle::new(), self)
+    }
+}
+
+impl Content for &String {
+    fn styled(self) -> StyledContent<String> {
+        self.as_str().styled()
+    }
+}
+
+impl<D: Display> Content for StyledContent<D> {
+    fn styled(self) -> StyledContent<String> {
+        StyledContent::new(*self.style(), self.content().to_string())
+    }
+}
+
+impl<D: Display> Content for &StyledContent<D> {
+    fn styled(self) -> StyledContent<String> {
+        StyledContent::new(*self.style(), self.content().to_string())
+    }
+}
+
 // Painter module. All drawing/painting to the terminal screen should be done from here.
+// Everything is drawn to a back buffer first, flush sends only the cells that changed since
+// the last flush to the backend.
 pub struct Painter {
-    stdout: io::Stdout,
+    backend: Box<dyn Backend>,
+    // What is being drawn.
+    back: Buffer,
+    // What the backend shows.
+    front: Buffer,
+    // Style and cursor position the backend has, cursor is None if it's unknown.
+    style: ContentStyle,
+    cursor: Option<(u16, u16)>,
 }
 
 impl Painter {
-    pub fn new(stdout: io::Stdout) -> Painter {
-        Painter { stdout }
+    pub fn new(backend: Box<dyn Backend>) -> Painter {
+        let size = backend.size().unwrap_or((0, 0));
+
+        Painter {
+            backend,
+            back: Buffer::new(size),
+            front: Buffer::new(size),
+            style: ContentStyle::new(),
+            cursor: None,
+        }
+    }
+
+    // Size of the screen, not of the buffers (they only change size when cleared).
+    pub fn size(&self) -> crossterm::Result<(u16, u16)> {
+        self.backend.size()
     }
 
     pub fn start(&mut self) -> crossterm::Result<()> {
-        execute!(
-            self.stdout,
-            EnterAlternateScreen,
-            cursor::Hide,
-            DisableLineWrap,
-            EnableMouseCapture
-        )
+        self.backend.start()?;
+
+        let size = self.backend.size()?;
+
+        self.back = Buffer::new(size);
+        self.clear_screen(size)
     }
 
     pub fn stop(&mut self) -> crossterm::Result<()> {
-        self.stdout.execute(DisableMouseCapture)?;
-
-        // Exhauste all events before closing.
-        // If this is not done there will be some text
-        // written in the terminal when the app closes.
-        // I assume this text is because there are some queued events.
-        loop {
-            if poll(Duration::from_millis(100))? {
-                read()?;
-            } else {
-                break;
-            }
-        }
+        self.set_style(ContentStyle::new())?;
+
+        self.backend.stop()
+    }
 
-        execute!(
-            self.stdout,
-            EnableLineWrap,
-            cursor::Show,
-            LeaveAlternateScreen
-        )?;
+    // Prints content to a specific position.
+    pub fn write<C: Content>(&mut self, x: u16, y: u16, content: C) -> crossterm::Result<()> {
+        self.back.move_to(x, y);
+        self.back.print(content.styled());
 
         Ok(())
     }
 
-    // Prints content to a specific position.
-    pub fn write<D: Display>(&mut self, x: u16, y: u16, content: D) -> crossterm::Result<()> {
-        queue!(self.stdout, cursor::MoveTo(x, y), Print(content))
+    // Moves the cursor without printing, to be followed by write_in_place.
+    pub fn move_to(&mut self, x: u16, y: u16) -> crossterm::Result<()> {
+        self.back.move_to(x, y);
+
+        Ok(())
     }
 
     // Prints content without moving the cursor.
-    pub fn write_in_place<D: Display>(&mut self, content: D) -> crossterm::Result<()> {
-        self.stdout.queue(Print(content))?;
+    pub fn write_in_place<C: Content>(&mut self, content: C) -> crossterm::Result<()> {
+        self.back.print(content.styled());
 
         Ok(())
     }
 
     // Fill area starting from position (x, y) with (width, height) size with {fill} characters.
-    pub fn _fill<D: Display>(
+    pub fn fill<C: Content>(
         &mut self,
         x: u16,
         y: u16,
         width: u16,
         height: u16,
-        fill: D,
+        fill: C,
     ) -> crossterm::Result<()> {
-        for i in 0..height {
-            self.stdout.queue(cursor::MoveTo(x, y + i))?;
+        let fill = fill.styled();
+        let row = StyledContent::new(*fill.style(), fill.content().repeat(usize::from(width)));
 
-            for _ in 0..width {
-                self.stdout.queue(Print(&fill))?;
-            }
+        for i in 0..height {
+            self.write(x, y + i, row.clone())?;
         }
 
         Ok(())
@@ -134,48 +201,303 @@ impl Painter {
             return Ok(());
         }
 
-        queue!(
-            self.stdout,
-            cursor::MoveTo(x, y),
-            Print(symbols::TOP_LEFT),
-            Print(horizontal.repeat(usize::from(width - 2))),
-            Print(symbols::TOP_RIGHT)
-        )?;
+        self.write(x, y, symbols::TOP_LEFT)?;
+        self.write_in_place(horizontal.repeat(usize::from(width - 2)))?;
+        self.write_in_place(symbols::TOP_RIGHT)?;
 
         for i in 1..(height - 1) {
-            queue!(
-                self.stdout,
-                cursor::MoveTo(x, y + i),
-                Print(vertical),
-                cursor::MoveToColumn(x + (width - 1)),
-                Print(vertical)
-            )?;
-        }
-
-        queue!(
-            self.stdout,
-            cursor::MoveTo(x, y + (height - 1)),
-            Print(symbols::BOTTOM_LEFT),
-            Print(horizontal.repeat(usize::from(width - 2))),
-            Print(symbols::BOTTOM_RIGHT)
-        )?;
+            self.write(x, y + i, vertical)?;
+            self.write(x + (width - 1), y + i, vertical)?;
+        }
+
+        self.write(x, y + (height - 1), symbols::BOTTOM_LEFT)?;
+        self.write_in_place(horizontal.repeat(usize::from(width - 2)))?;
+        self.write_in_place(symbols::BOTTOM_RIGHT)?;
 
         Ok(())
     }
 
     pub fn set_attribute(&mut self, attribute: Attribute) -> crossterm::Result<()> {
-        self.stdout.queue(SetAttribute(attribute))?;
+        self.back.set_attribute(attribute);
+
+        Ok(())
+    }
+
+    pub fn set_foreground(&mut self, color: Color) -> crossterm::Result<()> {
+        self.back.set_foreground(color);
 
         Ok(())
     }
 
+    // Clears everything drawn, nothing changes on screen until flush.
+    // If the screen size changed, the screen is cleared right away and drawn again on flush,
+    // because the terminal may have moved things around.
     pub fn clear(&mut self) -> crossterm::Result<()> {
-        self.stdout.queue(Clear(ClearType::All))?;
+        let size = self.backend.size()?;
+
+        if size != self.front.size() {
+            self.clear_screen(size)?;
+        }
+
+        self.back = Buffer::new(size);
+
+        Ok(())
+    }
+
+    fn clear_screen(&mut self, size: (u16, u16)) -> crossterm::Result<()> {
+        // Some terminals clear with the current background color.
+        self.set_style(ContentStyle::new())?;
+        self.backend.clear()?;
+
+        self.front = Buffer::new(size);
+        self.cursor = None;
 
         Ok(())
     }
 
+    // Sends cells that changed since the last flush to the backend.
     pub fn flush(&mut self) -> crossterm::Result<()> {
-        self.stdout.flush()
+        let (width, height) = self.front.size();
+
+        for y in 0..height {
+            for x in 0..width {
+                let (Some(cell), Some(old)) = (self.back.cell(x, y), self.front.cell(x, y)) else {
+                    continue;
+                };
+
+                if cell == old {
+                    continue;
+                }
+
+                if self.cursor != Some((x, y)) {
+                    self.backend.move_to(x, y)?;
+                }
+
+                self.set_style(*cell.style())?;
+                self.backend.print(&cell.content().to_string())?;
+
+                self.front.set(x, y, cell);
+                self.cursor = Some((x + 1, y));
+            }
+        }
+
+        self.backend.flush()
+    }
+
+    // Changes the backend style to {style}, sending only what is different.
+    fn set_style(&mut self, style: ContentStyle) -> crossterm::Result<()> {
+        // Attributes can only be removed by resetting everything.
+        if self.style.attributes & style.attributes != self.style.attributes {
+            self.backend.set_attribute(Attribute::Reset)?;
+            self.style = ContentStyle::new();
+        }
+
+        for attribute in Attribute::iterator() {
+            if style.attributes.has(attribute) && !self.style.attributes.has(attribute) {
+                self.backend.set_attribute(attribute)?;
+            }
+        }
+
+        if style.foreground_color != self.style.foreground_color {
+            self.backend
+                .set_foreground(style.foreground_color.unwrap_or(Color::Reset))?;
+        }
+
+        if style.background_color != self.style.background_color {
+            self.backend
+                .set_background(style.background_color.unwrap_or(Color::Reset))?;
+        }
+
+        if style.underline_color != self.style.underline_color {
+            self.backend
+                .set_underline_color(style.underline_color.unwrap_or(Color::Reset))?;
+        }
+
+        self.style = style;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crossterm::style::Stylize;
+
+    use super::{memory::MemoryBackend, *};
+
+    fn painter(width: u16, height: u16) -> (Painter, MemoryBackend) {
+        let backend = MemoryBackend::new(width, height);
+
+        (Painter::new(Box::new(backend.clone())), backend)
+    }
+
+    // Backend that keeps a list of what it was asked to do.
+    #[derive(Clone, Default)]
+    struct Recorder {
+        calls: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl Recorder {
+        fn record(&self, call: String) -> crossterm::Result<()> {
+            self.calls.borrow_mut().push(call);
+
+            Ok(())
+        }
+
+        // Calls since the last time this was called.
+        fn take(&self) -> Vec<String> {
+            self.calls.take()
+        }
+    }
+
+    impl Backend for Recorder {
+        fn start(&mut self) -> crossterm::Result<()> {
+            Ok(())
+        }
+
+        fn stop(&mut self) -> crossterm::Result<()> {
+            Ok(())
+        }
+
+        fn size(&self) -> crossterm::Result<(u16, u16)> {
+            Ok((4, 2))
+        }
+
+        fn move_to(&mut self, x: u16, y: u16) -> crossterm::Result<()> {
+            self.record(format!("move {} {}", x, y))
+        }
+
+        fn print(&mut self, text: &str) -> crossterm::Result<()> {
+            self.record(format!("print {}", text))
+        }
+
+        fn set_attribute(&mut self, attribute: Attribute) -> crossterm::Result<()> {
+            self.record(format!("{:?}", attribute))
+        }
+
+        fn set_foreground(&mut self, color: Color) -> crossterm::Result<()> {
+            self.record(format!("fg {:?}", color))
+        }
+
+        fn set_background(&mut self, color: Color) -> crossterm::Result<()> {
+            self.record(format!("bg {:?}", color))
+        }
+
+        fn set_underline_color(&mut self, color: Color) -> crossterm::Result<()> {
+            self.record(format!("underline {:?}", color))
+        }
+
+        fn clear(&mut self) -> crossterm::Result<()> {
+            self.record(String::from("clear"))
+        }
+
+        fn flush(&mut self) -> crossterm::Result<()> {
+            self.record(String::from("flush"))
+        }
+    }
+
+    #[test]
+    fn writes_clip_to_screen() {
+        let (mut painter, backend) = painter(6, 2);
+
+        painter.write(3, 1, "abcdef").unwrap();
+        painter.write(0, 0, 'x').unwrap();
+        painter.flush().unwrap();
+
+        assert_eq!(backend.line(0), "x     ");
+        assert_eq!(backend.line(1), "   abc");
+        assert_eq!(backend.cell(6, 0), None);
+    }
+
+    #[test]
+    fn styles_last_until_reset() {
+        let (mut painter, backend) = painter(4, 1);
+
+        painter.set_attribute(Attribute::Reverse).unwrap();
+        painter.write(0, 0, 'a').unwrap();
+        painter.write_in_place('b'.red()).unwrap();
+        painter.write_in_place('c').unwrap();
+        painter.write_in_place('d'.bold()).unwrap();
+        painter.set_attribute(Attribute::Reverse).unwrap();
+        painter.set_attribute(Attribute::Reset).unwrap();
+        painter.flush().unwrap();
+
+        assert_eq!(backend.cell(0, 0), Some('a'.reverse()));
+        assert_eq!(backend.cell(1, 0), Some('b'.red().reverse()));
+        // Color was reset after 'b', attribute is still set.
+        assert_eq!(backend.cell(2, 0), Some('c'.reverse()));
+        assert_eq!(backend.cell(3, 0), Some('d'.reverse().bold()));
+    }
+
+    #[test]
+    fn draw_box_and_clear() {
+        let (mut painter, backend) = painter(4, 3);
+
+        painter.draw_box(0, 0, 4, 3).unwrap();
+        painter.flush().unwrap();
+
+        assert_eq!(backend.line(0), "┌──┐");
+        assert_eq!(backend.line(1), "│  │");
+        assert_eq!(backend.line(2), "└──┘");
+
+        painter.clear().unwrap();
+
+        // Screen only changes on flush.
+        assert_eq!(backend.line(1), "│  │");
+
+        painter.flush().unwrap();
+
+        assert_eq!(backend.line(1), "    ");
+    }
+
+    #[test]
+    fn flush_sends_only_changed_cells() {
+        let recorder = Recorder::default();
+        let mut painter = Painter::new(Box::new(recorder.clone()));
+
+        painter.write(0, 0, "abc").unwrap();
+        painter.flush().unwrap();
+
+        assert_eq!(
+            recorder.take(),
+            ["move 0 0", "print a", "print b", "print c", "flush"]
+        );
+
+        painter.clear().unwrap();
+        painter.write(0, 0, "abd").unwrap();
+        painter.write(1, 1, 'e').unwrap();
+        painter.flush().unwrap();
+
+        assert_eq!(
+            recorder.take(),
+            ["move 2 0", "print d", "move 1 1", "print e", "flush"]
+        );
+
+        painter.flush().unwrap();
+
+        assert_eq!(recorder.take(), ["flush"]);
+    }
+
+    #[test]
+    fn flush_sends_only_style_changes() {
+        let recorder = Recorder::default();
+        let mut painter = Painter::new(Box::new(recorder.clone()));
+
+        painter.write(0, 0, 'a'.red()).unwrap();
+        painter.write_in_place('b'.red()).unwrap();
+        painter.write_in_place('c'.red().reverse()).unwrap();
+        painter.write_in_place('d'.blue()).unwrap();
+        painter.flush().unwrap();
+
+        assert_eq!(
+            recorder.take(),
+            [
+                "move 0 0", "fg Red", "print a", "print b", "Reverse", "print c",
+                // Removing an attribute resets colors too.
+                "Reset", "fg Blue", "print d", "flush"
+            ]
+        );
     }
 }
@@ -0,0 +1,136 @@
+use crossterm::style::{Attribute, Color, ContentStyle, StyledContent};
+
+// Grid of styled characters that is drawn the same way as a terminal with line wrap disabled:
+// text is printed at a cursor, with a style set by attributes and colors that lasts until reset.
+#[derive(Clone)]
+pub struct Buffer {
+    size: (u16, u16),
+    cells: Vec<Vec<StyledContent<char>>>,
+    cursor: (u16, u16),
+    style: ContentStyle,
+}
+
+impl Buffer {
+    // Buffer of {size} with blank cells.
+    pub fn new(size: (u16, u16)) -> Buffer {
+        let (width, height) = size;
+
+        Buffer {
+            size,
+            cells: vec![vec![blank(); usize::from(width)]; usize::from(height)],
+            cursor: (0, 0),
+            style: ContentStyle::new(),
+        }
+    }
+
+    pub fn size(&self) -> (u16, u16) {
+        self.size
+    }
+
+    // Character and style at (x, y), None if it's outside the buffer.
+    pub fn cell(&self, x: u16, y: u16) -> Option<StyledContent<char>> {
+        self.cells
+            .get(usize::from(y))
+            .and_then(|row| row.get(usize::from(x)))
+            .copied()
+    }
+
+    // Replaces the cell at (x, y), if it's inside the buffer.
+    pub fn set(&mut self, x: u16, y: u16, cell: StyledContent<char>) {
+        if let Some(old) = self
+            .cells
+            .get_mut(usize::from(y))
+            .and_then(|row| row.get_mut(usize::from(x)))
+        {
+            *old = cell;
+        }
+    }
+
+    // Characters in row {y}, without style.
+    #[cfg(test)]
+    pub fn line(&self, y: u16) -> String {
+        self.cells.get(usize::from(y)).map_or(String::new(), |row| {
+            row.iter().map(|cell| *cell.content()).collect()
+        })
+    }
+
+    pub fn move_to(&mut self, x: u16, y: u16) {
+        self.cursor = (x, y);
+    }
+
+    // Prints at the cursor and moves it after the content, what doesn't fit is lost.
+    pub fn print(&mut self, content: StyledContent<String>) {
+        let own = content.style();
+        let style = ContentStyle {
+            foreground_color: own.foreground_color.or(self.style.foreground_color),
+            background_color: own.background_color.or(self.style.background_color),
+            underline_color: own.underline_color.or(self.style.underline_color),
+            attributes: self.style.attributes | own.attributes,
+        };
+        let (x, y) = self.cursor;
+
+        if let Some(row) = self.cells.get_mut(usize::from(y)) {
+            let cells = row.iter_mut().skip(usize::from(x));
+
+            for (cell, character) in cells.zip(content.content().chars()) {
+                *cell = StyledContent::new(style, character);
+            }
+        }
+
+        self.cursor.0 = x.saturating_add(content.content().chars().count() as u16);
+
+        // Styled content resets what it changed after printing, and attributes reset everything.
+        if !own.attributes.is_empty() {
+            self.style = ContentStyle::new();
+        } else {
+            if own.background_color.is_some() {
+                self.style.background_color = None;
+            }
+            if own.foreground_color.is_some() || own.underline_color.is_some() {
+                self.style.foreground_color = None;
+            }
+        }
+    }
+
+    pub fn set_attribute(&mut self, attribute: Attribute) {
+        match attribute {
+            Attribute::Reset => self.style = ContentStyle::new(),
+            attribute => self.style.attributes.set(attribute),
+        }
+    }
+
+    pub fn set_foreground(&mut self, color: Color) {
+        self.style.foreground_color = color_option(color);
+    }
+
+    #[cfg(test)]
+    pub fn set_background(&mut self, color: Color) {
+        self.style.background_color = color_option(color);
+    }
+
+    #[cfg(test)]
+    pub fn set_underline_color(&mut self, color: Color) {
+        self.style.underline_color = color_option(color);
+    }
+
+    // Makes every cell blank, cursor and style are kept.
+    #[cfg(test)]
+    pub fn clear(&mut self) {
+        for row in &mut self.cells {
+            row.fill(blank());
+        }
+    }
+}
+
+// Cell of a cleared screen.
+fn blank() -> StyledContent<char> {
+    StyledContent::new(ContentStyle::new(), ' ')
+}
+
+// Color::Reset means going back to the default color, which is None in ContentStyle.
+fn color_option(color: Color) -> Option<Color> {
+    match color {
+        Color::Reset => None,
+        color => Some(color),
+    }
+}
@@ -0,0 +1,113 @@
+use std::{
+    cell::{Cell, RefCell},
+    io,
+    rc::Rc,
+};
+
+use crossterm::style::{Attribute, Color, ContentStyle, StyledContent};
+
+use super::{buffer::Buffer, Backend};
+
+// Screen kept in memory instead of a terminal, so tests can check what was drawn.
+// Clones share the same screen, so a test can keep one and give the other to a Painter.
+#[derive(Clone)]
+pub struct MemoryBackend {
+    screen: Rc<RefCell<Buffer>>,
+    // Whether flushing fails, like writing to a terminal that was closed.
+    closed: Rc<Cell<bool>>,
+}
+
+impl MemoryBackend {
+    pub fn new(width: u16, height: u16) -> MemoryBackend {
+        MemoryBackend {
+            screen: Rc::new(RefCell::new(Buffer::new((width, height)))),
+            closed: Rc::new(Cell::new(false)),
+        }
+    }
+
+    // Changes the screen size like a terminal window being resized, everything on it is lost.
+    pub fn resize(&self, width: u16, height: u16) {
+        *self.screen.borrow_mut() = Buffer::new((width, height));
+    }
+
+    // Makes every flush from now on fail with an I/O error.
+    pub fn close(&self) {
+        self.closed.set(true);
+    }
+
+    // Character and style at (x, y), None if it's outside the screen.
+    pub fn cell(&self, x: u16, y: u16) -> Option<StyledContent<char>> {
+        self.screen.borrow().cell(x, y)
+    }
+
+    // Characters in row {y}, without style.
+    pub fn line(&self, y: u16) -> String {
+        self.screen.borrow().line(y)
+    }
+}
+
+impl Backend for MemoryBackend {
+    fn start(&mut self) -> crossterm::Result<()> {
+        Ok(())
+    }
+
+    fn stop(&mut self) -> crossterm::Result<()> {
+        Ok(())
+    }
+
+    fn size(&self) -> crossterm::Result<(u16, u16)> {
+        Ok(self.screen.borrow().size())
+    }
+
+    fn move_to(&mut self, x: u16, y: u16) -> crossterm::Result<()> {
+        self.screen.borrow_mut().move_to(x, y);
+
+        Ok(())
+    }
+
+    fn print(&mut self, text: &str) -> crossterm::Result<()> {
+        let content = StyledContent::new(ContentStyle::new(), text.to_string());
+
+        self.screen.borrow_mut().print(content);
+
+        Ok(())
+    }
+
+    fn set_attribute(&mut self, attribute: Attribute) -> crossterm::Result<()> {
+        self.screen.borrow_mut().set_attribute(attribute);
+
+        Ok(())
+    }
+
+    fn set_foreground(&mut self, color: Color) -> crossterm::Result<()> {
+        self.screen.borrow_mut().set_foreground(color);
+
+        Ok(())
+    }
+
+    fn set_background(&mut self, color: Color) -> crossterm::Result<()> {
+        self.screen.borrow_mut().set_background(color);
+
+        Ok(())
+    }
+
+    fn set_underline_color(&mut self, color: Color) -> crossterm::Result<()> {
+        self.screen.borrow_mut().set_underline_color(color);
+
+        Ok(())
+    }
+
+    fn clear(&mut self) -> crossterm::Result<()> {
+        self.screen.borrow_mut().clear();
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> crossterm::Result<()> {
+        if self.closed.get() {
+            return Err(io::Error::from_raw_os_error(libc::EIO));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
+
+use crossterm::{
+    cursor,
+    event::{poll, read, DisableMouseCapture, EnableMouseCapture},
+    execute, queue,
+    style::{
+        Attribute, Color, Print, SetAttribute, SetBackgroundColor, SetForegroundColor,
+        SetUnderlineColor,
+    },
+    terminal::{
+        disable_raw_mode, size, Clear, ClearType, DisableLineWrap, EnableLineWrap,
+        EnterAlternateScreen, LeaveAlternateScreen,
+    },
+    ExecutableCommand, QueueableCommand,
+};
+
+use super::Backend;
+
+// Draws to the terminal through stdout, commands are queued until flush.
+pub struct StdoutBackend {
+    stdout: io::Stdout,
+}
+
+impl StdoutBackend {
+    pub fn new(stdout: io::Stdout) -> StdoutBackend {
+        StdoutBackend { stdout }
+    }
+}
+
+// Gives the terminal back like stop and disable_raw_mode, without needing the backend.
+// Used when the app can't stop normally (e.g. in a panic hook), so events aren't drained.
+pub fn restore_terminal() -> crossterm::Result<()> {
+    execute!(
+        io::stdout(),
+        SetAttribute(Attribute::Reset),
+        DisableMouseCapture,
+        EnableLineWrap,
+        cursor::Show,
+        LeaveAlternateScreen
+    )?;
+
+    disable_raw_mode()
+}
+
+impl Backend for StdoutBackend {
+    fn start(&mut self) -> crossterm::Result<()> {
+        execute!(
+            self.stdout,
+            EnterAlternateScreen,
+            cursor::Hide,
+            DisableLineWrap,
+            EnableMouseCapture
+        )
+    }
+
+    fn stop(&mut self) -> crossterm::Result<()> {
+        self.stdout.execute(DisableMouseCapture)?;
+
+        // Exhauste all events before closing.
+        // If this is not done there will be some text
+        // written in the terminal when the app closes.
+        // I assume this text is because there are some queued events.
+        loop {
+            if poll(Duration::from_millis(100))? {
+                read()?;
+            } else {
+                break;
+            }
+        }
+
+        execute!(
+            self.stdout,
+            EnableLineWrap,
+            cursor::Show,
+            LeaveAlternateScreen
+        )?;
+
+        Ok(())
+    }
+
+    fn size(&self) -> crossterm::Result<(u16, u16)> {
+        size()
+    }
+
+    fn move_to(&mut self, x: u16, y: u16) -> crossterm::Result<()> {
+        self.stdout.queue(cursor::MoveTo(x, y))?;
+
+        Ok(())
+    }
+
+    fn print(&mut self, text: &str) -> crossterm::Result<()> {
+        queue!(self.stdout, Print(text))
+    }
+
+    fn set_attribute(&mut self, attribute: Attribute) -> crossterm::Result<()> {
+        self.stdout.queue(SetAttribute(attribute))?;
+
+        Ok(())
+    }
+
+    fn set_foreground(&mut self, color: Color) -> crossterm::Result<()> {
+        self.stdout.queue(SetForegroundColor(color))?;
+
+        Ok(())
+    }
+
+    fn set_background(&mut self, color: Color) -> crossterm::Result<()> {
+        self.stdout.queue(SetBackgroundColor(color))?;
+
+        Ok(())
+    }
+
+    fn set_underline_color(&mut self, color: Color) -> crossterm::Result<()> {
+        self.stdout.queue(SetUnderlineColor(color))?;
+
+        Ok(())
+    }
+
+    fn clear(&mut self) -> crossterm::Result<()> {
+        self.stdout.queue(Clear(ClearType::All))?;
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> crossterm::Result<()> {
+        self.stdout.flush()
+    }
+}
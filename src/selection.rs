@@ -0,0 +1,39 @@
+use std::cmp::{max, min};
+
+// Rectangle between two corners, in content positions. Corners can be in any order,
+// e.g. start is where a drag started and end is where the pointer is now.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Selection {
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+}
+
+impl Selection {
+    pub fn new(start: (usize, usize), end: (usize, usize)) -> Selection {
+        Selection { start, end }
+    }
+
+    // Returns top left and bottom right corners (inclusive).
+    pub fn bounds(&self) -> ((usize, usize), (usize, usize)) {
+        let (start_x, start_y) = self.start;
+        let (end_x, end_y) = self.end;
+
+        (
+            (min(start_x, end_x), min(start_y, end_y)),
+            (max(start_x, end_x), max(start_y, end_y)),
+        )
+    }
+
+    // Width and height, in content cells.
+    pub fn size(&self) -> (usize, usize) {
+        let ((x1, y1), (x2, y2)) = self.bounds();
+
+        (x2 - x1 + 1, y2 - y1 + 1)
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        let ((x1, y1), (x2, y2)) = self.bounds();
+
+        (x1..=x2).contains(&x) && (y1..=y2).contains(&y)
+    }
+}
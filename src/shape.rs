@@ -0,0 +1,135 @@
+// Content positions covered by shapes, painted by Document drawing operations.
+
+use crate::selection::Selection;
+
+// Returns all positions on the line from {from} to {to}, both included (Bresenham's algorithm).
+pub fn line(from: (usize, usize), to: (usize, usize)) -> Vec<(usize, usize)> {
+    let (mut x, mut y) = (from.0 as i64, from.1 as i64);
+    let (last_x, last_y) = (to.0 as i64, to.1 as i64);
+
+    let dx = (last_x - x).abs();
+    let dy = -(last_y - y).abs();
+    let step_x = if x < last_x { 1 } else { -1 };
+    let step_y = if y < last_y { 1 } else { -1 };
+    let mut error = dx + dy;
+
+    let mut points = vec![(x as usize, y as usize)];
+
+    while x != last_x || y != last_y {
+        let double_error = error * 2;
+
+        if double_error >= dy {
+            error += dy;
+            x += step_x;
+        }
+
+        if double_error <= dx {
+            error += dx;
+            y += step_y;
+        }
+
+        points.push((x as usize, y as usize));
+    }
+
+    points
+}
+
+// Returns positions of the rectangle with {selection} bounds, only its border if not {filled}.
+pub fn rectangle(selection: Selection, filled: bool) -> Vec<(usize, usize)> {
+    let ((x1, y1), (x2, y2)) = selection.bounds();
+
+    (y1..=y2)
+        .flat_map(|y| (x1..=x2).map(move |x| (x, y)))
+        .filter(|&(x, y)| filled || x == x1 || x == x2 || y == y1 || y == y2)
+        .collect()
+}
+
+// Returns positions of the ellipse that fits in {selection} bounds, only its outline if not {filled}.
+pub fn ellipse(selection: Selection, filled: bool) -> Vec<(usize, usize)> {
+    let ((x1, y1), (x2, y2)) = selection.bounds();
+
+    // Cells are inside if their center is, radii reach the outer edges of the border cells.
+    let center = ((x1 + x2) as f64 / 2.0, (y1 + y2) as f64 / 2.0);
+    let radius = ((x2 - x1 + 1) as f64 / 2.0, (y2 - y1 + 1) as f64 / 2.0);
+    let inside = |x: usize, y: usize| {
+        let dx = (x as f64 - center.0) / radius.0;
+        let dy = (y as f64 - center.1) / radius.1;
+
+        (x1..=x2).contains(&x) && (y1..=y2).contains(&y) && dx * dx + dy * dy <= 1.0
+    };
+
+    // Outline cells are inside cells next to an outside one.
+    let outline = |x: usize, y: usize| {
+        x == x1
+            || x == x2
+            || y == y1
+            || y == y2
+            || !inside(x - 1, y)
+            || !inside(x + 1, y)
+            || !inside(x, y - 1)
+            || !inside(x, y + 1)
+    };
+
+    (y1..=y2)
+        .flat_map(|y| (x1..=x2).map(move |x| (x, y)))
+        .filter(|&(x, y)| inside(x, y) && (filled || outline(x, y)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Draws {points} in a grid of {width}x{height}, one string per row.
+    fn grid(points: &[(usize, usize)], width: usize, height: usize) -> Vec<String> {
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| if points.contains(&(x, y)) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn line_goes_both_ways() {
+        assert_eq!(line((0, 0), (3, 1)), [(0, 0), (1, 0), (2, 1), (3, 1)]);
+        assert_eq!(line((3, 1), (0, 0)), [(3, 1), (2, 1), (1, 0), (0, 0)]);
+        assert_eq!(line((2, 2), (2, 2)), [(2, 2)]);
+    }
+
+    #[test]
+    fn rectangle_border() {
+        let points = rectangle(Selection::new((3, 2), (0, 0)), false);
+
+        assert_eq!(grid(&points, 4, 3), ["####", "#..#", "####"]);
+        assert_eq!(rectangle(Selection::new((0, 0), (3, 2)), true).len(), 12);
+    }
+
+    #[test]
+    fn ellipse_outline_and_fill() {
+        let selection = Selection::new((0, 0), (8, 4));
+
+        assert_eq!(
+            grid(&ellipse(selection, false), 9, 5),
+            [
+                "..#####..",
+                "##.....##",
+                "#.......#",
+                "##.....##",
+                "..#####.."
+            ]
+        );
+        assert_eq!(
+            grid(&ellipse(selection, true), 9, 5),
+            [
+                "..#####..",
+                "#########",
+                "#########",
+                "#########",
+                "..#####.."
+            ]
+        );
+        assert_eq!(ellipse(Selection::new((5, 5), (5, 5)), false), [(5, 5)]);
+    }
+}
@@ -0,0 +1,21 @@
+// Characters content is displayed with, in modes where it isn't stored as text.
+
+// Pixels
+pub const FULL_BLOCK: char = '█';
+pub const UPPER_HALF_BLOCK: char = '▀';
+pub const LOWER_HALF_BLOCK: char = '▄';
+
+// Braille
+pub const BRAILLE_BLANK: char = '\u{2800}';
+pub const BRAILLE_FULL: char = '⣿';
+// (column, row, bit) of each dot in a braille character.
+pub const BRAILLE_DOTS: [(usize, usize, u32); 8] = [
+    (0, 0, 0x01),
+    (0, 1, 0x02),
+    (0, 2, 0x04),
+    (1, 0, 0x08),
+    (1, 1, 0x10),
+    (1, 2, 0x20),
+    (0, 3, 0x40),
+    (1, 3, 0x80),
+];
@@ -1,3 +1,5 @@
+use std::{env, path::PathBuf};
+
 pub trait DiffOrZero {
     fn diff_or_zero(&self, other: &Self) -> Self;
 }
@@ -45,3 +47,14 @@ impl AddSubOrZero for usize {
         self + usize::from(other.unsigned_abs())
     }
 }
+
+// Directory for paint-tui files inside the XDG base directory from {variable},
+// or inside {fallback} in the home directory if it's not set (or not absolute).
+pub fn xdg_dir(variable: &str, fallback: &str) -> Option<PathBuf> {
+    let base = match env::var_os(variable).map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => dir,
+        _ => PathBuf::from(env::var_os("HOME")?).join(fallback),
+    };
+
+    Some(base.join("paint-tui"))
+}